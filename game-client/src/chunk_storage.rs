@@ -0,0 +1,36 @@
+use std::io;
+
+use game_interface::Storage;
+
+use game_state::ChunkStorage;
+
+use sized_matrix::Vector;
+
+pub struct KeyValueChunkStorage<TStorage: Storage> {
+	storage: TStorage,
+	prefix: String,
+}
+
+impl<TStorage: Storage> KeyValueChunkStorage<TStorage> {
+	pub fn new(storage: TStorage, prefix: String) -> Self {
+		Self {
+			storage,
+			prefix,
+		}
+	}
+	
+	fn key(&self, chunk: Vector<i32, 2>) -> String {
+		format!("{}.chunk.{}.{}", self.prefix, chunk[0], chunk[1])
+	}
+}
+
+impl<TStorage: Storage> ChunkStorage for KeyValueChunkStorage<TStorage> {
+	fn load(&mut self, chunk: Vector<i32, 2>) -> io::Result<Option<Vec<u8>>> {
+		Ok(self.storage.load(&self.key(chunk)))
+	}
+	
+	fn store(&mut self, chunk: Vector<i32, 2>, data: &[u8]) -> io::Result<()> {
+		let key = self.key(chunk);
+		self.storage.store(&key, data).map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+	}
+}
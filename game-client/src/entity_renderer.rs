@@ -0,0 +1,130 @@
+use game_interface::Canvas;
+
+use game_state::{Facing, World};
+
+use super::WorldRenderer;
+
+use lib::Colour;
+
+use sized_matrix::Vector;
+use higher_order_functions::Map;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Pose {
+	Idle,
+	Walk,
+	Dig,
+}
+
+impl Pose {
+	fn frames(self) -> usize {
+		match self {
+			Pose::Idle => 1,
+			Pose::Walk => 4,
+			Pose::Dig => 2,
+		}
+	}
+}
+
+// Remembers when the pose last changed, so each animation starts from its first frame
+pub struct Animation {
+	pose: Pose,
+	start: f64,
+}
+
+impl Animation {
+	pub fn new() -> Self {
+		Self {
+			pose: Pose::Idle,
+			start: 0.,
+		}
+	}
+	
+	// Returns the frame to draw, with time in seconds
+	pub fn update(&mut self, pose: Pose, time: f64) -> usize {
+		if pose != self.pose {
+			self.pose = pose;
+			self.start = time;
+		}
+		((time - self.start) * Self::FRAME_RATE).floor() as usize % pose.frames()
+	}
+	
+	const FRAME_RATE: f64 = 8.;
+}
+
+// Something drawn over the world, filling a box centred on pos in world coordinates
+pub struct Entity {
+	pub pos: Vector<f64, 2>,
+	pub size: Vector<f64, 2>,
+	pub facing: Facing,
+	pub pose: Pose,
+	pub frame: usize,
+	pub colour: Colour,
+}
+
+// Entities are queued up during the tick and drawn together on top of the world
+pub struct EntityRenderer {
+	entities: Vec<Entity>,
+}
+
+impl EntityRenderer {
+	pub fn new() -> Self {
+		Self {
+			entities: Vec::new(),
+		}
+	}
+	
+	pub fn push(&mut self, entity: Entity) {
+		self.entities.push(entity);
+	}
+	
+	// Uses the same camera as the world renderer, so entities don't drift against the tiles between whole pixels
+	pub fn draw_to<TCanvas: Canvas>(&mut self, dest: &mut TCanvas, world_renderer: &WorldRenderer<TCanvas>, world: &World, player: Vector<f64, 2>) {
+		for entity in self.entities.drain(..) {
+			let light = world_renderer.light(world, entity.pos.map(|x| x.floor() as i32));
+			let top_left = entity.pos - entity.size / 2.;
+			for &(colour, [x, y, width, height]) in figure(&entity).iter() {
+				// Parts are laid out facing right, and mirrored to face left
+				let x = if entity.facing == Facing::Left { 1. - x - width } else { x };
+				let pos = top_left + Vector::vector([x * entity.size[0], y * entity.size[1]]);
+				let size = Vector::vector([width * entity.size[0], height * entity.size[1]]);
+				dest.fill_rect(lit(colour, light), world_renderer.world_to_screen(pos, player), size * world_renderer.scale());
+			}
+		}
+	}
+}
+
+const PACK: Colour = Colour::grey(120);
+const HELMET: Colour = Colour::grey(235);
+const VISOR: Colour = Colour::rgb(30, 50, 80);
+const BOOTS: Colour = Colour::grey(50);
+
+// A spacesuit as coloured rectangles, each x, y, width and height as fractions of the entity's size
+fn figure(entity: &Entity) -> Vec<(Colour, [f64; 4])> {
+	let arm = lit(entity.colour, 0.8);
+	// How far the legs and arm are swung forward
+	let stride = match (entity.pose, entity.frame) {
+		(Pose::Walk, 1) => 0.15,
+		(Pose::Walk, 3) => -0.15,
+		_ => 0.,
+	};
+	let arm_rect = match (entity.pose, entity.frame) {
+		(Pose::Dig, 0) => [0.5, 0.34, 0.6, 0.08],
+		(Pose::Dig, _) => [0.5, 0.46, 0.6, 0.08],
+		_ => [0.45 - stride, 0.36, 0.2, 0.26],
+	};
+	vec![
+		(PACK, [0., 0.32, 0.2, 0.3]),
+		(BOOTS, [0.2 - stride, 0.66, 0.3, 0.34]),
+		(BOOTS, [0.5 + stride, 0.66, 0.3, 0.34]),
+		(entity.colour, [0.15, 0.3, 0.7, 0.38]),
+		(HELMET, [0.2, 0., 0.6, 0.3]),
+		(VISOR, [0.5, 0.08, 0.3, 0.12]),
+		(arm, arm_rect),
+	]
+}
+
+fn lit(colour: Colour, light: f64) -> Colour {
+	let channel = |x: u8| (x as f64 * light).round() as u8;
+	Colour::rgba(channel(colour.r), channel(colour.g), channel(colour.b), colour.a)
+}
@@ -1,62 +1,179 @@
 use super::*;
 
+use std::{
+	cell::RefCell,
+	rc::Rc,
+	str::FromStr,
+};
+
 use sized_matrix::Vector;
+use higher_order_functions::Map;
 use num_traits::Zero;
 
 use game_interface::{
+	Action,
+	ActionState,
+	Audio,
 	Canvas,
+	Channel,
+	ClipId,
+	DeadZone,
+	Gamepad,
+	GamepadAxis,
+	GamepadEvent,
+	InputMap,
 	Keys,
+	KeysWithGamepad,
+	Pointer,
+	PointerButton,
+	Sound,
+	Storage,
 	Timer,
+	Touch,
 	SmoothingQuality,
 };
 
 use game_state::{
+	ChunkStorage,
 	GameRules,
 	Player,
 	World,
 };
 
-use lib::Logger;
+use lib::{Colour, Logger};
+
+// Everything the game needs from the platform it runs on
+pub struct Platform<TTimer, TCanvas, TKeys, TPointer, TGamepad, TTouch, TAudio, TStorage> {
+	pub timer: TTimer,
+	pub canvas: TCanvas,
+	pub keys: TKeys,
+	pub pointer: TPointer,
+	pub gamepad: TGamepad,
+	pub touch: TTouch,
+	pub audio: TAudio,
+	pub storage: TStorage,
+}
 
 pub struct Game<
 	TTimer: 'static + Timer<Self>,
 	TCanvas: 'static + Canvas,
 	TKeys: 'static + Keys,
+	TPointer: 'static + Pointer,
+	TGamepad: 'static + Gamepad,
+	TTouch: 'static + Touch,
+	TAudio: 'static + Audio,
 > {
 	timer: TTimer,
 	canvas: TCanvas,
 	keys: TKeys,
+	pointer: TPointer,
+	gamepad: TGamepad,
+	touch: TTouch,
+	touch_controls: TouchControls,
+	audio: TAudio,
+	dig_sound: ClipId,
+	place_sound: ClipId,
+	dead_zone: DeadZone,
+	input_map: InputMap,
+	actions: ActionState,
+	settings: Box<dyn Storage>,
 	world_renderer: WorldRenderer<TCanvas>,
-	rules: GameRules,
+	entity_renderer: EntityRenderer,
+	map_renderer: MapRenderer<TCanvas>,
+	player_animation: Animation,
+	// When the player last dug or placed a block, in seconds
+	last_dig: Option<f64>,
+	rules: &'static GameRules,
 	animation: Option<TTimer::TAnimation>,
+	autosave: Option<TTimer::TInterval>,
+	chunk_storage: Option<Box<dyn ChunkStorage>>,
 	world: Option<World>,
 	player: Option<Player>,
+	held_block: Option<u16>,
+	// Digging and placing waiting for the next fixed step
+	edits: Vec<Edit>,
+	view_pos: Option<Vector<f64, 2>>,
+	last_time: Option<f64>,
+	accumulator: f64,
 }
 
 impl<
 	TTimer: 'static + Timer<Self>,
 	TCanvas: 'static + Canvas,
 	TKeys: 'static + Keys,
-> Game<TTimer, TCanvas, TKeys> {
-	pub fn new(timer: TTimer, canvas: TCanvas, keys: TKeys) -> Self {
+	TPointer: 'static + Pointer,
+	TGamepad: 'static + Gamepad,
+	TTouch: 'static + Touch,
+	TAudio: 'static + Audio,
+> Game<TTimer, TCanvas, TKeys, TPointer, TGamepad, TTouch, TAudio> {
+	pub fn new<TStorage: 'static + Storage>(platform: Platform<TTimer, TCanvas, TKeys, TPointer, TGamepad, TTouch, TAudio, TStorage>) -> Self {
+		let Platform { timer, canvas, keys, pointer, gamepad, touch, mut audio, storage } = platform;
+		let storage = Rc::new(RefCell::new(storage));
+		let input_map = Self::load_input_map(&storage);
 		Self {
 			timer,
 			keys,
-			world_renderer: WorldRenderer::new(canvas.size()),
+			pointer,
+			gamepad,
+			touch,
+			touch_controls: TouchControls::new(),
+			dig_sound: audio.load_clip(Self::DIG_SOUND),
+			place_sound: audio.load_clip(Self::PLACE_SOUND),
+			audio,
+			dead_zone: DeadZone::new(Self::DEAD_ZONE_INNER, Self::DEAD_ZONE_OUTER),
+			input_map,
+			actions: ActionState::new(),
+			settings: Box::new(Rc::clone(&storage)),
+			world_renderer: WorldRenderer::new(canvas.size(), canvas.pixel_ratio()),
+			entity_renderer: EntityRenderer::new(),
+			map_renderer: MapRenderer::new(),
+			player_animation: Animation::new(),
+			last_dig: None,
 			canvas,
-			rules: GameRules::load(),
+			rules: GameRules::bundled(),
 			animation: None,
+			autosave: None,
+			chunk_storage: Some(Box::new(KeyValueChunkStorage::new(storage, format!("world.{}", Self::SEED)))),
 			world: None,
 			player: None,
+			held_block: None,
+			edits: Vec::new(),
+			view_pos: None,
+			last_time: None,
+			accumulator: 0.,
 		}
 	}
 	
-	pub fn start(&'static mut self) {
+	fn load_input_map<TStorage: Storage>(storage: &TStorage) -> InputMap {
+		let text = match storage.load(Self::INPUT_MAP_KEY).map(String::from_utf8) {
+			Some(Ok(text)) => text,
+			Some(Err(_)) => {
+				Logger::error("Saved input map is not valid UTF-8");
+				return InputMap::standard();
+			},
+			None => return InputMap::standard(),
+		};
+		InputMap::from_str(&text).unwrap_or_else(|err| {
+			Logger::error(&err.to_string());
+			InputMap::standard()
+		})
+	}
+	
+	pub fn start(&mut self) {
 		self.canvas.set_smoothing_quality(SmoothingQuality::None);
-		self.world = Some(World::new(&self.rules, 123));
-		self.player = Some(Player::new(Vector::zero()));
+		let mut world = match self.chunk_storage.take() {
+			Some(storage) => World::with_storage(self.rules, Self::SEED, storage),
+			None => World::new(self.rules, Self::SEED),
+		};
+		self.player = Some(Player::spawn(&mut world, 0));
+		self.world = Some(world);
 		self.animation = Some(self.timer.set_animation(Self::tick));
+		self.autosave = Some(self.timer.set_interval(Self::AUTOSAVE_MS, Self::save));
 		self.keys.start();
+		self.pointer.start();
+		self.gamepad.start();
+		self.touch.start();
+		self.audio.start();
 		Logger::info("Started");
 	}
 	
@@ -64,15 +181,265 @@ impl<
 		&mut self.keys
 	}
 	
-	pub fn tick(&mut self, _time: f64) {
+	pub fn pointer(&mut self) -> &mut TPointer {
+		&mut self.pointer
+	}
+	
+	pub fn gamepad(&mut self) -> &mut TGamepad {
+		&mut self.gamepad
+	}
+	
+	pub fn touch(&mut self) -> &mut TTouch {
+		&mut self.touch
+	}
+	
+	pub fn audio(&mut self) -> &mut TAudio {
+		&mut self.audio
+	}
+	
+	pub fn input_map(&self) -> &InputMap {
+		&self.input_map
+	}
+	
+	pub fn set_input_map(&mut self, input_map: InputMap) {
+		if let Err(err) = self.settings.store(Self::INPUT_MAP_KEY, input_map.to_ron().as_bytes()) {
+			Logger::error(&format!("Failed to save input map: {}", err));
+		}
+		self.input_map = input_map;
+	}
+	
+	pub fn canvas(&self) -> &TCanvas {
+		&self.canvas
+	}
+	
+	pub fn canvas_mut(&mut self) -> &mut TCanvas {
+		&mut self.canvas
+	}
+	
+	pub fn player(&self) -> Option<&Player> {
+		self.player.as_ref()
+	}
+	
+	pub fn world_mut(&mut self) -> Option<&mut World> {
+		self.world.as_mut()
+	}
+	
+	pub fn save(&mut self) {
+		if let Some(world) = self.world.as_mut() {
+			match world.save() {
+				Ok(0) => (),
+				Ok(count) => Logger::debug(&format!("Saved {} chunks", count)),
+				Err(err) => Logger::error(&format!("Failed to save world: {}", err)),
+			}
+		}
+	}
+	
+	pub fn tick(&mut self, time: f64) {
+		let elapsed = match self.last_time {
+			Some(last_time) => (time - last_time) / 1000.,
+			None => 0.,
+		};
+		self.last_time = Some(time);
+		// Clamp to avoid spiralling after the tab has been in the background
+		self.accumulator = (self.accumulator + elapsed).min(Self::MAX_FRAME_TIME);
+		
+		if let Some(size) = self.canvas.take_resize() {
+			self.world_renderer.resize(size, self.canvas.pixel_ratio());
+		}
+		
+		self.audio.poll();
+		for err in self.audio.take_errors() {
+			Logger::error(&err.to_string());
+		}
+		
+		self.gamepad.poll();
+		for event in self.gamepad.take_events() {
+			match event {
+				GamepadEvent::Connected(index) => Logger::info(&format!("Gamepad {} connected", index)),
+				GamepadEvent::Disconnected(index) => Logger::info(&format!("Gamepad {} disconnected", index)),
+			}
+		}
+		
+		self.actions.update(&self.input_map, &KeysWithGamepad::new(&self.keys, &self.gamepad));
+		self.touch_controls.update(self.touch.touches(), self.canvas.size(), time);
+		// Keys and the sticks are added so any can be used, clamped so using several isn't faster
+		let stick = self.dead_zone.stick(&self.gamepad, GamepadAxis::LeftX, GamepadAxis::LeftY) + self.touch_controls.stick();
+		let jump = self.actions.is_held(Action::Jump) || self.touch_controls.is_jumping();
+		let mut movement = Vector::vector([
+			(self.actions.axis(Action::MoveLeft, Action::MoveRight) + stick[0]).clamp(-1., 1.),
+			if jump { -1. } else { (self.actions.axis(Action::MoveUp, Action::MoveDown) + stick[1]).clamp(-1., 1.) },
+		]);
+		
 		let player = self.player.as_mut().unwrap();
 		let world = self.world.as_mut().unwrap();
-		player.tick(
-			Vector::vector([
-				if self.keys.is_pressed("d") { 1. } else { 0. } - if self.keys.is_pressed("a") { 1. } else { 0. },
-				if self.keys.is_pressed("s") { 1. } else { 0. } - if self.keys.is_pressed("w") { 1. } else { 0. },
-			])
-		);
-		self.world_renderer.draw_to(&mut self.canvas, world, player.pos());
+		
+		if self.actions.was_pressed(Action::Map) {
+			self.map_renderer.toggle(player.pos());
+		}
+		// While the map is open the controls move the map instead of the player, who stands still
+		let wheel = self.pointer.take_wheel();
+		if self.map_renderer.is_open() {
+			self.map_renderer.pan(movement, elapsed);
+			let mut zoom = 0.;
+			if self.actions.was_pressed(Action::ZoomIn) {
+				zoom += 1.;
+			}
+			if self.actions.was_pressed(Action::ZoomOut) {
+				zoom -= 1.;
+			}
+			// Scrolling down zooms out, like most maps
+			if wheel != 0. {
+				zoom -= wheel.signum();
+			}
+			self.map_renderer.zoom_by(zoom);
+			movement = Vector::zero();
+		}
+		
+		let map_open = self.map_renderer.is_open();
+		if !map_open {
+			let target = target_block(player, movement);
+			if self.actions.was_pressed(Action::Dig) {
+				self.edits.push(Edit::Dig(target));
+			} else if self.actions.was_pressed(Action::Place) {
+				self.edits.push(Edit::Place(target));
+			}
+		}
+		
+		// Taps on the map are dropped rather than kept for when it closes
+		let taps = self.touch_controls.take_taps();
+		if let Some(view_pos) = self.view_pos.filter(|_| !map_open) {
+			// Buttons are checked every frame so dragging digs or places along the way
+			if let Some(screen) = self.pointer.position() {
+				let target = self.world_renderer.screen_to_tile(screen, view_pos);
+				if in_reach(player, target, self.rules.reach()) {
+					if self.pointer.is_pressed(PointerButton::Primary) {
+						self.edits.push(Edit::Dig(target));
+					} else if self.pointer.is_pressed(PointerButton::Secondary) {
+						self.edits.push(Edit::Place(target));
+					}
+				}
+			}
+			
+			for tap in taps {
+				let target = self.world_renderer.screen_to_tile(tap, view_pos);
+				if in_reach(player, target, self.rules.reach()) {
+					self.edits.push(if self.touch_controls.is_place_mode() { Edit::Place(target) } else { Edit::Dig(target) });
+				}
+			}
+		}
+		
+		let seconds = time / 1000.;
+		while self.accumulator >= Self::TICK {
+			// Edits wait for the next step rather than changing the world between steps, so every peer applies them at the same point
+			for edit in self.edits.drain(..) {
+				let (target, worked, clip) = match edit {
+					Edit::Dig(target) => (target, dig(world, target, &mut self.held_block), self.dig_sound),
+					Edit::Place(target) => (target, place(world, player, target, self.held_block), self.place_sound),
+				};
+				if worked {
+					player.face_towards(target[0] as f64 + 0.5);
+					self.last_dig = Some(seconds);
+					let offset = target.map(|x| x as f64 + 0.5) - player.pos();
+					self.audio.play(clip, Channel::Effects, Sound::at(offset, Self::SOUND_RANGE));
+				}
+			}
+			player.tick(world, movement, Self::TICK);
+			self.accumulator -= Self::TICK;
+		}
+		
+		let alpha = self.accumulator / Self::TICK;
+		let view_pos = player.interpolated_pos(alpha);
+		
+		let pose = if self.last_dig.map_or(false, |last_dig| seconds - last_dig < Self::DIG_POSE_TIME) {
+			Pose::Dig
+		} else if player.is_grounded() && player.vel()[0].abs() > Self::WALK_POSE_SPEED {
+			Pose::Walk
+		} else {
+			Pose::Idle
+		};
+		self.entity_renderer.push(Entity {
+			pos: view_pos,
+			size: player.size(),
+			facing: player.facing(),
+			pose,
+			frame: self.player_animation.update(pose, seconds),
+			colour: Self::SUIT,
+		});
+		
+		let changes = world.take_changes();
+		self.map_renderer.reveal(world, player.pos());
+		self.map_renderer.update(world, &changes);
+		
+		self.world_renderer.draw_to(&mut self.canvas, world, &changes, view_pos);
+		self.entity_renderer.draw_to(&mut self.canvas, &self.world_renderer, world, view_pos);
+		self.map_renderer.draw_to(&mut self.canvas, world.rules(), view_pos);
+		self.touch_controls.draw_to(&mut self.canvas);
+		self.view_pos = Some(view_pos);
 	}
+	
+	const SEED: u64 = 123;
+	const INPUT_MAP_KEY: &'static str = "settings.input";
+	const DEAD_ZONE_INNER: f64 = 0.2;
+	const DEAD_ZONE_OUTER: f64 = 0.95;
+	const AUTOSAVE_MS: u32 = 5000;
+	const TICK: f64 = 1. / 60.;
+	const MAX_FRAME_TIME: f64 = 0.25;
+	// How long the dig pose is held after digging or placing a block, in seconds
+	const DIG_POSE_TIME: f64 = 0.25;
+	const WALK_POSE_SPEED: f64 = 0.5;
+	const DIG_SOUND: &'static str = "sounds/dig.wav";
+	const PLACE_SOUND: &'static str = "sounds/place.wav";
+	// How far away in blocks an edit can still be heard
+	const SOUND_RANGE: f64 = 16.;
+	const SUIT: Colour = Colour::rgb(220, 110, 40);
+}
+
+#[derive(Copy, Clone)]
+enum Edit {
+	Dig(Vector<i32, 2>),
+	Place(Vector<i32, 2>),
+}
+
+// The block next to the player in the direction they are moving, or the one under their feet
+fn target_block(player: &Player, direction: Vector<f64, 2>) -> Vector<i32, 2> {
+	// Only the sign counts, so a stick pushed part way still reaches past the player's own tile
+	let direction = direction.map(|x| if x.abs() > 0.5 { x.signum() } else { 0. });
+	let direction = if direction == Vector::zero() { Vector::vector([0., 1.]) } else { direction };
+	let reach = player.size() / 2. + Vector::vector([0.5, 0.5]);
+	Vector::vector([
+		player.pos()[0] + direction[0] * reach[0],
+		player.pos()[1] + direction[1] * reach[1],
+	]).map(|x| x.floor() as i32)
+}
+
+fn in_reach(player: &Player, target: Vector<i32, 2>, reach: f64) -> bool {
+	let centre = target.map(|x| x as f64 + 0.5) - player.pos();
+	(centre[0] * centre[0] + centre[1] * centre[1]).sqrt() <= reach
+}
+
+// Both return whether anything was changed
+fn dig(world: &mut World, target: Vector<i32, 2>, held_block: &mut Option<u16>) -> bool {
+	if let Some(block) = world.dig(target) {
+		*held_block = Some(block);
+		true
+	} else {
+		false
+	}
+}
+
+fn place(world: &mut World, player: &Player, target: Vector<i32, 2>, held_block: Option<u16>) -> bool {
+	if let Some(block) = held_block {
+		if !world.get(target).solid && !overlaps(player, target) {
+			world.set(target, block);
+			return true;
+		}
+	}
+	false
+}
+
+fn overlaps(player: &Player, block: Vector<i32, 2>) -> bool {
+	let min = player.pos() - player.size() / 2.;
+	let max = player.pos() + player.size() / 2.;
+	(block[0] as f64) < max[0] && (block[0] + 1) as f64 > min[0] &&
+	(block[1] as f64) < max[1] && (block[1] + 1) as f64 > min[1]
 }
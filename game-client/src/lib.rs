@@ -1,5 +1,314 @@
+mod chunk_storage;
+pub use chunk_storage::*;
+
+mod entity_renderer;
+use entity_renderer::*;
+
 mod game;
 pub use game::*;
 
+mod light_map;
+use light_map::*;
+
+mod map_renderer;
+use map_renderer::*;
+
+mod touch_controls;
+use touch_controls::*;
+
 mod world_renderer;
 use world_renderer::*;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	use std::str::FromStr;
+	
+	use game_state::{Facing, GameRules, World};
+	use game_interface::{Channel, GamepadAxis, GamepadButton, PointerButton};
+	use lib::Colour;
+	use native_interface::{AudioEvent, Environment, ManualClock, ManualGamepad, ManualPointer, ManualTimer, ManualTouch, MemoryStorage, RecordingAudio, ScriptedKeys, SoftCanvas, Timeline};
+	use sized_matrix::Vector;
+	
+	#[test]
+	fn scrolled_view_matches_fresh_view() {
+		let rules = GameRules::bundled();
+		let mut world = World::new(rules, 123);
+		let size = Vector::vector([128, 96]);
+		let start = Vector::vector([0.3, -40.6]);
+		
+		let mut scrolled = WorldRenderer::<SoftCanvas>::new(size, 1.);
+		let mut scrolled_canvas = SoftCanvas::new(size);
+		scrolled.draw_to(&mut scrolled_canvas, &mut world, &[], start);
+		
+		for offset in [[3.25, 2.5], [-7.75, 1.], [0.5, -9.125], [20., 30.]].iter() {
+			let pos = start + Vector::vector(*offset);
+			scrolled.draw_to(&mut scrolled_canvas, &mut world, &[], pos);
+			
+			let mut fresh = WorldRenderer::<SoftCanvas>::new(size, 1.);
+			let mut fresh_canvas = SoftCanvas::new(size);
+			fresh.draw_to(&mut fresh_canvas, &mut world, &[], pos);
+			
+			assert!(scrolled_canvas.pixels() == fresh_canvas.pixels(), "Scrolling to {:?} left stale pixels", offset);
+		}
+	}
+	
+	#[test]
+	fn light_fades_with_depth() {
+		let rules = GameRules::bundled();
+		let mut world = World::new(rules, 123);
+		let mut renderer = WorldRenderer::<SoftCanvas>::new(Vector::vector([320, 240]), 1.);
+		let mut canvas = SoftCanvas::new(Vector::vector([320, 240]));
+		let lighting = rules.lighting();
+		
+		// High in the sky everything is lit, lamp or not
+		renderer.draw_to(&mut canvas, &mut world, &[], Vector::vector([0.5, -1000.5]));
+		assert_eq!(renderer.light(&world, Vector::vector([15, -1000])), 1.);
+		
+		// Deep underground only the lamp and glowing blocks light anything
+		renderer.draw_to(&mut canvas, &mut world, &[], Vector::vector([0.5, 1000.5]));
+		assert_eq!(renderer.light(&world, Vector::vector([0, 1000])), 1.);
+		for &(x, lamp) in [(5, lighting.lamp(5.)), (15, 0.)].iter() {
+			let pos = Vector::vector([x, 1000]);
+			assert!((renderer.light(&world, pos) - lighting.combine(lighting.min_ambient, lamp, world.light(pos))).abs() < 1e-9);
+		}
+	}
+	
+	struct Env;
+	
+	impl Environment for Env {
+		type TState = TestGame;
+	}
+	
+	// The game with every backend swapped for one the test controls
+	type TestGame = Game<ManualTimer<Env>, SoftCanvas, ScriptedKeys, ManualPointer, ManualGamepad, ManualTouch, RecordingAudio>;
+	
+	// Edits only happen on a fixed step, and 1000 / 60 ms frames can add up to just under a step and
+	// skip it, so whole millisecond frames a little over a step make sure every frame steps
+	fn test_clock() -> ManualClock<Env> {
+		let mut clock = ManualClock::new();
+		clock.set_frame_time(17.);
+		clock
+	}
+	
+	fn start_game(clock: &ManualClock<Env>, script: &str) -> TestGame {
+		let keys = ScriptedKeys::new(Timeline::from_str(script).unwrap());
+		let mut game = Game::new(Platform {
+			timer: clock.timer(),
+			canvas: SoftCanvas::new(Vector::vector([160, 120])),
+			keys,
+			pointer: ManualPointer::new(),
+			gamepad: ManualGamepad::new(),
+			touch: ManualTouch::new(),
+			audio: RecordingAudio::new(),
+			storage: MemoryStorage::new(),
+		});
+		game.start();
+		game
+	}
+	
+	#[test]
+	fn game_runs_headless() {
+		let clock = ManualClock::<Env>::new();
+		let mut game = start_game(&clock, "");
+		
+		clock.run_frames(&mut game, 1200);
+		
+		assert!((clock.now() - 20_000.).abs() < 1e-6);
+		assert!(game.player().unwrap().is_grounded());
+		assert!(game.canvas().pixels().iter().all(|pixel| pixel >> 24 == 0xFF));
+	}
+	
+	#[test]
+	fn actions_move_player() {
+		let clock = ManualClock::<Env>::new();
+		let mut game = start_game(&clock, "10 down KeyD\n10 down ShiftLeft\n70 up KeyD\n");
+		clock.run_frames(&mut game, 5);
+		let start = game.player().unwrap().pos();
+		
+		for _ in 0..120 {
+			clock.next_frame(&mut game);
+			game.keys().next_frame();
+		}
+		
+		assert!(game.player().unwrap().pos()[0] > start[0] + 1.);
+	}
+	
+	#[test]
+	fn screen_centre_is_player() {
+		let renderer = WorldRenderer::<SoftCanvas>::new(Vector::vector([160, 120]), 1.);
+		let player = Vector::vector([10.3, -4.8]);
+		let centre = renderer.screen_to_world(Vector::vector([80., 60.]), player);
+		
+		// The view is snapped to whole screen pixels
+		assert!((centre[0] - player[0]).abs() <= 0.5 / 8. && (centre[1] - player[1]).abs() <= 0.5 / 8.);
+		assert_eq!(renderer.screen_to_tile(Vector::vector([80., 60.]) + Vector::vector([16., 0.]), player), Vector::vector([12, -5]));
+	}
+	
+	#[test]
+	fn resize_redraws_whole_canvas() {
+		let clock = ManualClock::<Env>::new();
+		let mut game = start_game(&clock, "");
+		clock.run_frames(&mut game, 30);
+		
+		game.canvas_mut().resize(Vector::vector([203, 97]), 2.);
+		clock.next_frame(&mut game);
+		assert!(game.canvas().pixels().iter().all(|pixel| pixel >> 24 == 0xFF));
+		
+		// Tiles are twice as many screen pixels across, and the player stays in the middle
+		let renderer = WorldRenderer::<SoftCanvas>::new(Vector::vector([203, 97]), 2.);
+		let player = Vector::vector([10.3, -4.8]);
+		let centre = renderer.screen_to_world(Vector::vector([101.5, 48.5]), player);
+		assert!((centre[0] - player[0]).abs() <= 0.5 / 16. && (centre[1] - player[1]).abs() <= 0.5 / 16.);
+		let step = renderer.screen_to_world(Vector::vector([117.5, 48.5]), player) - centre;
+		assert!((step[0] - 1.).abs() < 1e-9 && step[1] == 0.);
+	}
+	
+	#[test]
+	fn player_is_drawn_facing_movement() {
+		let clock = ManualClock::<Env>::new();
+		let mut game = start_game(&clock, "10 down KeyA\n40 up KeyA\n");
+		clock.run_frames(&mut game, 30);
+		
+		// The suit fills the middle of the screen, just behind the arm, in daylight at the surface
+		assert_eq!(game.player().unwrap().facing(), Facing::Right);
+		assert_eq!(u32::from(game.canvas().pixel(Vector::vector([78, 60]))), u32::from(Colour::rgb(220, 110, 40)));
+		
+		for _ in 0..60 {
+			clock.next_frame(&mut game);
+			game.keys().next_frame();
+		}
+		assert_eq!(game.player().unwrap().facing(), Facing::Left);
+	}
+	
+	#[test]
+	fn map_shows_only_explored_chunks() {
+		let rules = GameRules::bundled();
+		let mut world = World::new(rules, 123);
+		let mut map = MapRenderer::<SoftCanvas>::new();
+		let mut canvas = SoftCanvas::new(Vector::vector([160, 120]));
+		
+		map.reveal(&mut world, Vector::vector([0.5, 0.5]));
+		assert!(map.is_explored(Vector::vector([0, 0])) && map.is_explored(Vector::vector([-1, -1])));
+		assert!(!map.is_explored(Vector::vector([-2, 0])));
+		
+		// Changes are kept up to date, one pixel per block
+		world.set(Vector::vector([3, 4]), rules.dug_block());
+		let changes = world.take_changes();
+		map.update(&mut world, &changes);
+		map.toggle(Vector::vector([3., 4.]));
+		map.draw_to(&mut canvas, rules, Vector::vector([0.5, 0.5]));
+		assert_eq!(u32::from(canvas.pixel(Vector::vector([80, 60]))), u32::from(rules.block(rules.dug_block()).colour));
+		assert_eq!(u32::from(canvas.pixel(Vector::vector([0, 60]))), u32::from(Colour::grey(0)));
+		
+		// Only chunks in view are drawn to canvases, however far the player has explored
+		for x in 0..20 {
+			map.reveal(&mut world, Vector::vector([x as f64 * 64., 0.5]));
+		}
+		map.toggle(Vector::vector([640.5, 0.5]));
+		map.draw_to(&mut canvas, rules, Vector::vector([640.5, 0.5]));
+		assert!(map.is_explored(Vector::vector([19, 0])));
+		assert!(map.cached_chunks() <= 4);
+	}
+	
+	#[test]
+	fn map_stops_player_moving() {
+		let clock = ManualClock::<Env>::new();
+		let mut game = start_game(&clock, "10 down KeyM\n11 up KeyM\n12 down KeyD\n");
+		clock.run_frames(&mut game, 5);
+		let start = game.player().unwrap().pos();
+		
+		for _ in 0..60 {
+			clock.next_frame(&mut game);
+			game.keys().next_frame();
+		}
+		
+		assert_eq!(game.player().unwrap().pos()[0], start[0]);
+	}
+	
+	// Lets the player land, then checks that the input, given the point on screen and tile just under the player's feet, digs there
+	fn assert_digs_under_player<F: FnOnce(&mut TestGame, &ManualClock<Env>, Vector<f64, 2>, Vector<i32, 2>)>(input: F) {
+		let clock = test_clock();
+		let mut game = start_game(&clock, "");
+		clock.run_frames(&mut game, 60);
+		
+		let player = game.player().unwrap();
+		let below = Vector::vector([player.pos()[0].floor() as i32, (player.pos()[1] + player.size()[1] / 2. + 0.5).floor() as i32]);
+		assert_ne!(game.world_mut().unwrap().get_id(below), GameRules::bundled().dug_block());
+		
+		// The player is drawn in the middle of the screen, 8 pixels per block
+		input(&mut game, &clock, Vector::vector([80., 60. + 8. * 1.4]), below);
+		
+		assert_eq!(game.world_mut().unwrap().get_id(below), GameRules::bundled().dug_block());
+		assert!(game.audio().events().iter().any(|event| matches!(event, AudioEvent::Played { clip, channel: Channel::Effects, .. } if clip == "sounds/dig.wav")));
+	}
+	
+	#[test]
+	fn pointer_digs_under_player() {
+		assert_digs_under_player(|game, clock, screen, _| {
+			game.pointer().move_to(screen);
+			game.pointer().press(PointerButton::Primary);
+			clock.next_frame(game);
+		});
+	}
+	
+	#[test]
+	fn half_pushed_stick_digs_under_player() {
+		assert_digs_under_player(|game, clock, _, _| {
+			game.gamepad().connect(0);
+			game.gamepad().set_axis(GamepadAxis::LeftY, 0.66);
+			game.gamepad().set_button(GamepadButton::RightTrigger, 1.);
+			clock.next_frame(game);
+		});
+	}
+	
+	#[test]
+	fn stick_moves_proportionally() {
+		let distance = |push: f64| {
+			let clock = ManualClock::<Env>::new();
+			let mut game = start_game(&clock, "");
+			clock.run_frames(&mut game, 5);
+			game.gamepad().connect(0);
+			game.gamepad().set_axis(GamepadAxis::LeftX, push);
+			let start = game.player().unwrap().pos()[0];
+			clock.run_frames(&mut game, 30);
+			game.player().unwrap().pos()[0] - start
+		};
+		
+		assert_eq!(distance(0.1), 0.);
+		let half = distance(0.575);
+		let full = distance(1.);
+		assert!(half > 0. && half < full * 0.75);
+	}
+	
+	#[test]
+	fn tap_digs_under_player() {
+		assert_digs_under_player(|game, clock, screen, below| {
+			// Nothing happens until the finger is lifted, since it might be a drag
+			game.touch().touch(0, screen);
+			clock.next_frame(game);
+			assert_ne!(game.world_mut().unwrap().get_id(below), GameRules::bundled().dug_block());
+			
+			game.touch().release(0);
+			clock.next_frame(game);
+		});
+	}
+	
+	#[test]
+	fn joystick_moves_player() {
+		let clock = ManualClock::<Env>::new();
+		let mut game = start_game(&clock, "");
+		clock.run_frames(&mut game, 5);
+		let start = game.player().unwrap().pos();
+		
+		// The joystick sits in the bottom left, a fifth of the height in from the corner
+		game.touch().touch(3, Vector::vector([22.5, 97.5]));
+		clock.next_frame(&mut game);
+		game.touch().touch(3, Vector::vector([37.5, 97.5]));
+		clock.run_frames(&mut game, 60);
+		
+		assert!(game.player().unwrap().pos()[0] > start[0] + 1.);
+		assert!(game.canvas().pixels().iter().all(|pixel| pixel >> 24 == 0xFF));
+	}
+}
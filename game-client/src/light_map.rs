@@ -0,0 +1,46 @@
+use game_state::World;
+
+use sized_matrix::Vector;
+use higher_order_functions::Map;
+
+// Ambient light for each tile the renderer has cached, moved along with it so only newly visible tiles are calculated
+pub struct LightMap {
+	pos: Vector<i32, 2>,
+	size: Vector<u32, 2>,
+	levels: Vec<f64>,
+}
+
+impl LightMap {
+	pub fn new(size: Vector<u32, 2>) -> Self {
+		Self {
+			pos: Vector::vector([i32::MIN, i32::MIN]) / 2,
+			size,
+			levels: vec![0.; size[0] as usize * size[1] as usize],
+		}
+	}
+	
+	pub fn scroll(&mut self, world: &World, pos: Vector<i32, 2>) {
+		let mut levels = Vec::with_capacity(self.levels.len());
+		for y in 0..self.size[1] as i32 {
+			for x in 0..self.size[0] as i32 {
+				let tile = pos + Vector::vector([x, y]);
+				levels.push(self.get(tile).unwrap_or_else(|| ambient(world, tile)));
+			}
+		}
+		self.pos = pos;
+		self.levels = levels;
+	}
+	
+	pub fn get(&self, pos: Vector<i32, 2>) -> Option<f64> {
+		let local = pos - self.pos;
+		if local[0] >= 0 && local[0] < self.size[0] as i32 && local[1] >= 0 && local[1] < self.size[1] as i32 {
+			Some(self.levels[local[1] as usize * self.size[0] as usize + local[0] as usize])
+		} else {
+			None
+		}
+	}
+}
+
+fn ambient(world: &World, pos: Vector<i32, 2>) -> f64 {
+	world.rules().lighting().ambient(world.depth(pos.map(|x| x as f64 + 0.5)))
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use game_interface::{
+	Canvas,
+	SmoothingQuality,
+};
+
+use game_state::{Chunk, GameRules, World};
+
+use lib::Colour;
+
+use sized_matrix::Vector;
+use higher_order_functions::Map;
+
+// A minimap in the top right, or a full screen map that can be panned and zoomed
+// Only chunks the player has been near are shown
+// Explored chunks are kept as block ids, and only those in view are drawn to canvases at one pixel per block
+pub struct MapRenderer<TCanvas: Canvas> {
+	explored: HashMap<Vector<i32, 2>, Vec<u16>>,
+	canvases: HashMap<Vector<i32, 2>, TCanvas>,
+	open: bool,
+	// The block in the middle of the full map
+	centre: Vector<f64, 2>,
+	// CSS pixels per block on the full map
+	zoom: f64,
+}
+
+impl<TCanvas: Canvas> MapRenderer<TCanvas> {
+	pub fn new() -> Self {
+		Self {
+			explored: HashMap::new(),
+			canvases: HashMap::new(),
+			open: false,
+			centre: Vector::vector([0., 0.]),
+			zoom: 1.,
+		}
+	}
+	
+	pub fn is_open(&self) -> bool {
+		self.open
+	}
+	
+	// Opening always starts centred on the player
+	pub fn toggle(&mut self, player: Vector<f64, 2>) {
+		self.open = !self.open;
+		self.centre = player;
+	}
+	
+	#[cfg(test)]
+	pub fn is_explored(&self, chunk_pos: Vector<i32, 2>) -> bool {
+		self.explored.contains_key(&chunk_pos)
+	}
+	
+	#[cfg(test)]
+	pub fn cached_chunks(&self) -> usize {
+		self.canvases.len()
+	}
+	
+	// Movement from -1 to 1 on each axis, moving the same speed across the screen at any zoom
+	pub fn pan(&mut self, movement: Vector<f64, 2>, dt: f64) {
+		self.centre = self.centre + movement * (Self::PAN_SPEED / self.zoom * dt);
+	}
+	
+	// Each step doubles or halves the size of the blocks
+	pub fn zoom_by(&mut self, steps: f64) {
+		self.zoom = (self.zoom * 2f64.powf(steps)).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+	}
+	
+	pub fn reveal(&mut self, world: &mut World, player: Vector<f64, 2>) {
+		let min = chunk_of(player.map(|x| x - Self::REVEAL_DISTANCE));
+		let max = chunk_of(player.map(|x| x + Self::REVEAL_DISTANCE));
+		for y in min[1]..=max[1] {
+			for x in min[0]..=max[0] {
+				let chunk_pos = Vector::vector([x, y]);
+				self.explored.entry(chunk_pos).or_insert_with(|| {
+					let mut ids = Vec::with_capacity(Chunk::SIZE * Chunk::SIZE);
+					for local_y in 0..Chunk::I_SIZE {
+						for local_x in 0..Chunk::I_SIZE {
+							ids.push(world.get_id(chunk_pos * Chunk::I_SIZE + Vector::vector([local_x, local_y])));
+						}
+					}
+					ids
+				});
+			}
+		}
+	}
+	
+	// Changes outside explored chunks are left until they are revealed, when the whole chunk is drawn anyway
+	pub fn update(&mut self, world: &mut World, changes: &[Vector<i32, 2>]) {
+		for &pos in changes {
+			let chunk_pos = chunk_of(pos.map(f64::from));
+			if let Some(ids) = self.explored.get_mut(&chunk_pos) {
+				let local = pos.map(|x| x.rem_euclid(Chunk::I_SIZE));
+				let id = world.get_id(pos);
+				ids[(local[1] * Chunk::I_SIZE + local[0]) as usize] = id;
+				if let Some(canvas) = self.canvases.get_mut(&chunk_pos) {
+					canvas.fill_rect(world.rules().block(id).colour, local.map(f64::from), Vector::vector([1., 1.]));
+				}
+			}
+		}
+	}
+	
+	pub fn draw_to(&mut self, dest: &mut TCanvas, rules: &GameRules, player: Vector<f64, 2>) {
+		let pixel_ratio = dest.pixel_ratio();
+		let (centre, scale, pos, size) = if self.open {
+			(self.centre, self.zoom * pixel_ratio, Vector::vector([0., 0.]), dest.size().map(f64::from))
+		} else {
+			// Small screens get a smaller minimap, so it doesn't cover the player
+			let screen = dest.size();
+			let size = (Self::MINIMAP_SIZE * pixel_ratio).min(screen[0].min(screen[1]) as f64 * Self::MINIMAP_MAX_FRACTION).round();
+			let margin = (Self::MINIMAP_MARGIN * pixel_ratio).round();
+			let pos = Vector::vector([screen[0] as f64 - size - margin, margin]);
+			(player, Self::MINIMAP_ZOOM * pixel_ratio, pos, Vector::vector([size, size]))
+		};
+		
+		dest.fill_rect(if self.open { Colour::grey(0) } else { Colour::rgba(0, 0, 0, 160) }, pos, size);
+		
+		// The blocks covered, which are drawn from whichever chunks have been explored
+		let min = centre - size / (2. * scale);
+		let max = centre + size / (2. * scale);
+		let chunk_min = chunk_of(min);
+		let chunk_max = chunk_of(max);
+		let in_view = |chunk_pos: Vector<i32, 2>| (0..2).all(|i| chunk_pos[i] >= chunk_min[i] && chunk_pos[i] <= chunk_max[i]);
+		self.canvases.retain(|&chunk_pos, _| in_view(chunk_pos));
+		for y in chunk_min[1]..=chunk_max[1] {
+			for x in chunk_min[0]..=chunk_max[0] {
+				let chunk_pos = Vector::vector([x, y]);
+				if let Some(ids) = self.explored.get(&chunk_pos) {
+					let canvas = self.canvases.entry(chunk_pos).or_insert_with(|| chunk_canvas(rules, ids));
+					let origin = (chunk_pos * Chunk::I_SIZE).map(f64::from);
+					let from = Vector::vector([min[0].max(origin[0]), min[1].max(origin[1])]);
+					let to = Vector::vector([max[0].min(origin[0] + Chunk::SIZE as f64), max[1].min(origin[1] + Chunk::SIZE as f64)]);
+					if from[0] < to[0] && from[1] < to[1] {
+						dest.draw_image_segment_scaled(canvas.as_image(), from - origin, to - from, pos + (from - min) * scale, (to - from) * scale);
+					}
+				}
+			}
+		}
+		
+		let marker = (Self::MARKER_SIZE * pixel_ratio).round();
+		let marker_pos = pos + (player - min) * scale - Vector::vector([marker, marker]) / 2.;
+		if marker_pos[0] >= pos[0] && marker_pos[1] >= pos[1] && marker_pos[0] + marker <= pos[0] + size[0] && marker_pos[1] + marker <= pos[1] + size[1] {
+			dest.fill_rect(Colour::rgb(255, 60, 60), marker_pos, Vector::vector([marker, marker]));
+		}
+	}
+	
+	// In blocks, enough to cover the screen around the player
+	const REVEAL_DISTANCE: f64 = 32.;
+	// In CSS pixels
+	const PAN_SPEED: f64 = 400.;
+	const MIN_ZOOM: f64 = 0.25;
+	const MAX_ZOOM: f64 = 8.;
+	const MINIMAP_SIZE: f64 = 96.;
+	const MINIMAP_MAX_FRACTION: f64 = 0.25;
+	const MINIMAP_MARGIN: f64 = 8.;
+	const MINIMAP_ZOOM: f64 = 1.;
+	const MARKER_SIZE: f64 = 3.;
+}
+
+fn chunk_canvas<TCanvas: Canvas>(rules: &GameRules, ids: &[u16]) -> TCanvas {
+	let mut canvas = TCanvas::create(Vector::vector([Chunk::SIZE as u32, Chunk::SIZE as u32]));
+	canvas.set_smoothing_quality(SmoothingQuality::None);
+	for (i, &id) in ids.iter().enumerate() {
+		let local = Vector::vector([(i % Chunk::SIZE) as f64, (i / Chunk::SIZE) as f64]);
+		canvas.fill_rect(rules.block(id).colour, local, Vector::vector([1., 1.]));
+	}
+	canvas
+}
+
+fn chunk_of(pos: Vector<f64, 2>) -> Vector<i32, 2> {
+	pos.map(|x| (x / Chunk::SIZE as f64).floor() as i32)
+}
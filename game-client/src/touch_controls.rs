@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+
+use game_interface::{
+	Canvas,
+	DeadZone,
+	TouchPoint,
+};
+
+use lib::Colour;
+
+use sized_matrix::Vector;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Role {
+	Joystick,
+	Jump,
+	Mode,
+	// Anywhere else, so a tap on the world
+	World,
+}
+
+struct Tracked {
+	role: Role,
+	start: Vector<f64, 2>,
+	pos: Vector<f64, 2>,
+	start_time: f64,
+}
+
+// An on-screen joystick in the bottom left, jump and dig/place buttons in the bottom right, and taps on the world
+// Each finger keeps the control it first touched, so dragging off the joystick doesn't start a tap
+pub struct TouchControls {
+	tracked: BTreeMap<u32, Tracked>,
+	dead_zone: DeadZone,
+	// Hidden until the first touch, so mouse and keyboard players don't see them
+	visible: bool,
+	place_mode: bool,
+	stick: Vector<f64, 2>,
+	taps: Vec<Vector<f64, 2>>,
+}
+
+impl TouchControls {
+	pub fn new() -> Self {
+		Self {
+			tracked: BTreeMap::new(),
+			dead_zone: DeadZone::new(Self::DEAD_ZONE_INNER, 1.),
+			visible: false,
+			place_mode: false,
+			stick: Vector::vector([0., 0.]),
+			taps: Vec::new(),
+		}
+	}
+	
+	pub fn update(&mut self, touches: &[TouchPoint], size: Vector<u32, 2>, time: f64) {
+		let layout = Layout::new(size);
+		
+		for touch in touches {
+			match self.tracked.get_mut(&touch.id) {
+				Some(tracked) => tracked.pos = touch.pos,
+				None => {
+					self.visible = true;
+					self.tracked.insert(touch.id, Tracked {
+						role: layout.hit(touch.pos),
+						start: touch.pos,
+						pos: touch.pos,
+						start_time: time,
+					});
+				},
+			}
+		}
+		
+		let released: Vec<u32> = self.tracked.keys().copied().filter(|id| touches.iter().all(|touch| touch.id != *id)).collect();
+		for id in released {
+			let tracked = self.tracked.remove(&id).unwrap();
+			match tracked.role {
+				Role::Mode => self.place_mode = !self.place_mode,
+				Role::World => {
+					let moved = tracked.pos - tracked.start;
+					if time - tracked.start_time <= Self::TAP_MS && length(moved) <= Self::TAP_DISTANCE {
+						self.taps.push(tracked.start);
+					}
+				},
+				_ => (),
+			}
+		}
+		
+		self.stick = match self.tracked.values().find(|tracked| tracked.role == Role::Joystick) {
+			Some(tracked) => self.dead_zone.apply((tracked.pos - layout.joystick) / layout.radius),
+			None => Vector::vector([0., 0.]),
+		};
+	}
+	
+	// From -1 to 1 on each axis, with y positive downwards
+	pub fn stick(&self) -> Vector<f64, 2> {
+		self.stick
+	}
+	
+	pub fn is_jumping(&self) -> bool {
+		self.tracked.values().any(|tracked| tracked.role == Role::Jump)
+	}
+	
+	pub fn is_place_mode(&self) -> bool {
+		self.place_mode
+	}
+	
+	// Screen positions tapped since the last call
+	pub fn take_taps(&mut self) -> Vec<Vector<f64, 2>> {
+		std::mem::take(&mut self.taps)
+	}
+	
+	pub fn draw_to<TCanvas: Canvas>(&self, canvas: &mut TCanvas) {
+		if !self.visible { return; }
+		
+		let layout = Layout::new(canvas.size());
+		let square = |canvas: &mut TCanvas, colour: Colour, centre: Vector<f64, 2>, half: f64| {
+			canvas.fill_rect(colour, centre - Vector::vector([half, half]), Vector::vector([half, half]) * 2.);
+		};
+		
+		square(canvas, Colour::rgba(255, 255, 255, 48), layout.joystick, layout.radius);
+		square(canvas, Colour::rgba(255, 255, 255, 128), layout.joystick + self.stick * layout.radius, layout.radius * 0.4);
+		
+		let jump_alpha = if self.is_jumping() { 160 } else { 80 };
+		square(canvas, Colour::rgba(255, 255, 255, jump_alpha), layout.jump, layout.button);
+		
+		let mode_colour = if self.place_mode { Colour::rgba(80, 160, 255, 128) } else { Colour::rgba(255, 160, 64, 128) };
+		square(canvas, mode_colour, layout.mode, layout.button * 0.75);
+	}
+	
+	const DEAD_ZONE_INNER: f64 = 0.15;
+	const TAP_MS: f64 = 300.;
+	const TAP_DISTANCE: f64 = 12.;
+}
+
+// Where the controls are, scaled to the smaller side of the screen
+struct Layout {
+	joystick: Vector<f64, 2>,
+	radius: f64,
+	jump: Vector<f64, 2>,
+	mode: Vector<f64, 2>,
+	button: f64,
+}
+
+impl Layout {
+	fn new(size: Vector<u32, 2>) -> Self {
+		let (width, height) = (size[0] as f64, size[1] as f64);
+		let unit = width.min(height) / 8.;
+		Self {
+			joystick: Vector::vector([unit * 1.5, height - unit * 1.5]),
+			radius: unit,
+			jump: Vector::vector([width - unit * 1.25, height - unit * 1.25]),
+			mode: Vector::vector([width - unit * 3., height - unit * 1.]),
+			button: unit * 0.6,
+		}
+	}
+	
+	// Targets are a little larger than they are drawn, since fingers are imprecise
+	fn hit(&self, pos: Vector<f64, 2>) -> Role {
+		let within = |centre: Vector<f64, 2>, half: f64| {
+			let offset = pos - centre;
+			offset[0].abs() <= half && offset[1].abs() <= half
+		};
+		if length(pos - self.joystick) <= self.radius * 1.5 {
+			Role::Joystick
+		} else if within(self.jump, self.button * 1.25) {
+			Role::Jump
+		} else if within(self.mode, self.button) {
+			Role::Mode
+		} else {
+			Role::World
+		}
+	}
+}
+
+fn length(vector: Vector<f64, 2>) -> f64 {
+	(vector[0] * vector[0] + vector[1] * vector[1]).sqrt()
+}
@@ -9,6 +9,8 @@ use game_interface::{
 
 use game_state::World;
 
+use super::LightMap;
+
 use lib::{Logger, Colour};
 
 use sized_matrix::Vector;
@@ -19,13 +21,24 @@ use noise_fn::{HashNoise, Seedable, NoiseDomain};
 pub struct WorldRenderer<TCanvas: Canvas> {
 	canvas: TCanvas,
 	pos: Vector<i32, 2>,
+	// Whole tiles needed to cover the view
 	size: Vector<u32, 2>,
+	// Tiles visible on the screen, which don't have to be whole
+	view: Vector<f64, 2>,
+	// Screen pixels per tile
+	scale: f64,
+	light_map: LightMap,
+	// The tile the player's lamp was centred on when last drawn
+	lamp: Option<Vector<i32, 2>>,
 	noise: HashNoise,
 }
 
 impl<TCanvas: Canvas> WorldRenderer<TCanvas> {
-	pub fn new(size: Vector<u32, 2>) -> Self {
-		let size = size / Self::SCALE;
+	pub fn new(size: Vector<u32, 2>, pixel_ratio: f64) -> Self {
+		// Tiles are kept a whole number of screen pixels so they stay sharp
+		let scale = (Self::SCALE as f64 * pixel_ratio).round().max(1.);
+		let view = size.map(|x| x as f64 / scale);
+		let size = view.map(|x| x.ceil() as u32);
 		
 		let mut canvas = TCanvas::create(size + Vector::vector([1, 1]));
 		canvas.set_smoothing_quality(SmoothingQuality::None);
@@ -34,23 +47,35 @@ impl<TCanvas: Canvas> WorldRenderer<TCanvas> {
 			canvas,
 			pos: Vector::vector([i32::MIN, i32::MIN]) / 2,
 			size,
+			view,
+			scale,
+			light_map: LightMap::new(size + Vector::vector([1, 1])),
+			lamp: None,
 			noise: HashNoise::new().seed(1234),
 		}
 	}
 	
-	pub fn draw_to(&mut self, dest: &mut TCanvas, world: &mut World, player: Vector<f64, 2>) {
+	// Rebuilds the cached canvas for the new screen, so everything is redrawn by the next draw_to
+	pub fn resize(&mut self, size: Vector<u32, 2>, pixel_ratio: f64) {
+		*self = Self::new(size, pixel_ratio);
+	}
+	
+	// Changes are the blocks taken from the world this tick, light changes are taken here
+	pub fn draw_to(&mut self, dest: &mut TCanvas, world: &mut World, changes: &[Vector<i32, 2>], player: Vector<f64, 2>) {
 		let i_size = self.size.map(|x| x as i32);
-		let f_size = self.size.map(|x| x as f64);
-		let f_dest = player - f_size / 2.;
-		let f_canvas = f_dest.map(|x| x.floor());
+		let (f_canvas, f_draw) = self.camera(player);
 		let i_canvas = f_canvas.map(|x| x as i32);
-		let f_draw = ((f_dest - f_canvas) * Self::F_SCALE).map(|x| x.round()) / Self::F_SCALE;
 		let i_delta = i_canvas - self.pos;
 		let f_delta = i_delta.map(|x| x as f64);
 		
+		// Moved before scrolling, so newly visible tiles are lit by the lamp where it is now
+		let lamp = player.map(|x| x.floor() as i32);
+		let old_lamp = self.lamp.replace(lamp);
+		
 		if i_delta != Vector::zero() {
 			self.canvas.draw_self(-f_delta);
 			self.pos = i_canvas;
+			self.light_map.scroll(world, i_canvas);
 			
 			let (new_x_min, new_x_max) =
 				if i_delta[0] >= 0 { (
@@ -96,19 +121,95 @@ impl<TCanvas: Canvas> WorldRenderer<TCanvas> {
 			}
 		}
 		
-		dest.draw_image_segment_scaled(self.canvas.as_image(), f_draw, f_size, Vector::zero(), f_size * Self::F_SCALE);
+		// The lamp lights whole tiles, so it only needs redrawing when the player moves to another tile
+		if old_lamp != Some(lamp) {
+			let radius = world.rules().lighting().lamp_radius;
+			for centre in old_lamp.iter().chain(Some(&lamp)) {
+				self.redraw_around(world, *centre, radius);
+			}
+		}
+		
+		for pos in changes.iter().copied().chain(world.take_light_changes()) {
+			if self.in_view(pos) {
+				self.redraw(world, pos);
+			}
+		}
+		
+		dest.draw_image_segment_scaled(self.canvas.as_image(), f_draw, self.view, Vector::zero(), self.view * self.scale);
+	}
+	
+	// The tile at the top left of the cached canvas, and the offset into it that is drawn, rounded to whole screen pixels
+	fn camera(&self, player: Vector<f64, 2>) -> (Vector<f64, 2>, Vector<f64, 2>) {
+		let f_dest = player - self.view / 2.;
+		let f_canvas = f_dest.map(|x| x.floor());
+		let f_draw = ((f_dest - f_canvas) * self.scale).map(|x| x.round()) / self.scale;
+		(f_canvas, f_draw)
+	}
+	
+	// Converts a position on the destination canvas to world coordinates, given the player position passed to draw_to
+	pub fn screen_to_world(&self, pos: Vector<f64, 2>, player: Vector<f64, 2>) -> Vector<f64, 2> {
+		let (f_canvas, f_draw) = self.camera(player);
+		f_canvas + f_draw + pos / self.scale
+	}
+	
+	// The inverse of screen_to_world
+	pub fn world_to_screen(&self, pos: Vector<f64, 2>, player: Vector<f64, 2>) -> Vector<f64, 2> {
+		let (f_canvas, f_draw) = self.camera(player);
+		(pos - f_canvas - f_draw) * self.scale
+	}
+	
+	pub fn scale(&self) -> f64 {
+		self.scale
+	}
+	
+	pub fn screen_to_tile(&self, pos: Vector<f64, 2>, player: Vector<f64, 2>) -> Vector<i32, 2> {
+		self.screen_to_world(pos, player).map(|x| x.floor() as i32)
+	}
+	
+	pub fn in_view(&self, pos: Vector<i32, 2>) -> bool {
+		let local = pos - self.pos;
+		local[0] >= 0 && local[0] <= self.size[0] as i32 && local[1] >= 0 && local[1] <= self.size[1] as i32
+	}
+	
+	// From 0 to 1, only known for tiles in view
+	pub fn light(&self, world: &World, pos: Vector<i32, 2>) -> f64 {
+		let lighting = world.rules().lighting();
+		let ambient = self.light_map.get(pos).unwrap_or(1.);
+		let lamp = match self.lamp {
+			Some(lamp) => {
+				let offset = (pos - lamp).map(|x| x as f64);
+				lighting.lamp((offset[0] * offset[0] + offset[1] * offset[1]).sqrt())
+			},
+			None => 0.,
+		};
+		lighting.combine(ambient, lamp, world.light(pos))
+	}
+	
+	fn redraw_around(&mut self, world: &mut World, centre: Vector<i32, 2>, radius: f64) {
+		let reach = radius.ceil() as i32;
+		for y in (centre[1] - reach)..=(centre[1] + reach) {
+			for x in (centre[0] - reach)..=(centre[0] + reach) {
+				let pos = Vector::vector([x, y]);
+				if self.in_view(pos) {
+					self.redraw(world, pos);
+				}
+			}
+		}
 	}
 	
 	pub fn redraw(&mut self, world: &mut World, pos: Vector<i32, 2>) {
 		let local = pos - self.pos;
-		if local[0] >= 0 && local[0] <= self.size[0] as i32 && local[1] >= 0 && local[1] <= self.size[1] as i32 {
-			let block = world.get(pos);
+		if self.in_view(pos) {
+			// Loads the chunk first, so its light is there to read
+			let block = world.rules().block(world.get_id(pos));
+			let light = self.light(world, pos);
+			let lit = |channel: u8| (channel as f64 * light).round() as u8;
 			let mut noise = self.noise.noise(pos);
 			let extra = take_value(&mut noise, block.brightness_variation);
 			let colour = Colour::rgba(
-				block.colour.r + extra + take_value(&mut noise, block.colour_variation),
-				block.colour.g + extra + take_value(&mut noise, block.colour_variation),
-				block.colour.b + extra + take_value(&mut noise, block.colour_variation),
+				lit(block.colour.r + extra + take_value(&mut noise, block.colour_variation)),
+				lit(block.colour.g + extra + take_value(&mut noise, block.colour_variation)),
+				lit(block.colour.b + extra + take_value(&mut noise, block.colour_variation)),
 				block.colour.a,
 			);
 			self.canvas.fill_rect(colour, local.map(f64::from), Vector::vector([1., 1.]));
@@ -117,8 +218,8 @@ impl<TCanvas: Canvas> WorldRenderer<TCanvas> {
 		}
 	}
 	
+	// Screen pixels per tile at a pixel ratio of 1
 	const SCALE: u32 = 8;
-	const F_SCALE: f64 = Self::SCALE as f64;
 }
 
 fn take_value<T: PrimInt + AsPrimitive<u64>>(value: &mut u64, max: T) -> T where u64: AsPrimitive<T> {
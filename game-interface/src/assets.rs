@@ -0,0 +1,73 @@
+use std::fmt;
+
+use super::Canvas;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ImageId(pub usize);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LoadState {
+	Loading,
+	Loaded,
+	Failed,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct LoadProgress {
+	pub loaded: usize,
+	pub failed: usize,
+	pub total: usize,
+}
+
+impl LoadProgress {
+	pub fn is_done(&self) -> bool {
+		self.loaded + self.failed == self.total
+	}
+	
+	// From 0 to 1, counting failures as finished so a loading bar doesn't get stuck
+	pub fn fraction(&self) -> f64 {
+		if self.total == 0 { 1. } else { (self.loaded + self.failed) as f64 / self.total as f64 }
+	}
+}
+
+// Images load in the background, so loading gives an id straight away and the image can be drawn once it has loaded
+pub trait Assets<TCanvas: Canvas> {
+	// Loading the same path again gives the same id
+	fn load_image(&mut self, path: &str) -> ImageId;
+	
+	// Checks which loads have finished, call once per frame
+	fn poll(&mut self);
+	
+	fn state(&self, id: ImageId) -> LoadState;
+	
+	// None until the image has loaded
+	fn image(&self, id: ImageId) -> Option<TCanvas::TImage<'_>>;
+	
+	fn progress(&self) -> LoadProgress;
+	
+	// Loads that have failed since the last call
+	fn take_errors(&mut self) -> Vec<AssetError>;
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AssetError {
+	pub path: String,
+	pub reason: String,
+}
+
+impl AssetError {
+	pub fn new(path: &str, reason: &str) -> Self {
+		Self {
+			path: String::from(path),
+			reason: String::from(reason),
+		}
+	}
+}
+
+impl fmt::Display for AssetError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Could not load {}: {}", self.path, self.reason)
+	}
+}
+
+impl std::error::Error for AssetError { }
@@ -0,0 +1,73 @@
+use sized_matrix::Vector;
+
+use super::AssetError;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ClipId(pub usize);
+
+// A looping track that is playing, or waiting for its clip to load
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct TrackId(pub usize);
+
+// Each channel has its own volume, so effects and ambience can be turned down separately
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Channel {
+	Effects,
+	Ambience,
+}
+
+impl Channel {
+	pub const ALL: [Channel; 2] = [Channel::Effects, Channel::Ambience];
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Sound {
+	pub volume: f64,
+	// Playback rate, so 2 is an octave higher and twice as fast
+	pub pitch: f64,
+	// From -1 for the left speaker to 1 for the right
+	pub pan: f64,
+}
+
+impl Sound {
+	pub const NORMAL: Self = Self { volume: 1., pitch: 1., pan: 0. };
+	
+	// A sound at an offset from the player, fading out with distance and panned to the side it is on
+	pub fn at(offset: Vector<f64, 2>, range: f64) -> Self {
+		let distance = (offset[0] * offset[0] + offset[1] * offset[1]).sqrt();
+		Self {
+			volume: (1. - distance / range).max(0.),
+			pan: (offset[0] / range).clamp(-1., 1.),
+			..Self::NORMAL
+		}
+	}
+}
+
+pub trait Audio {
+	// Browsers only allow sound after the player has interacted with the page, so this waits for that
+	fn start(&self);
+	
+	// Loading the same path again gives the same id
+	fn load_clip(&mut self, path: &str) -> ClipId;
+	
+	// Starts any tracks whose clips have loaded, call once per frame
+	fn poll(&mut self);
+	
+	// Clips that haven't loaded yet are skipped, since a late sound effect is worse than none
+	fn play(&mut self, clip: ClipId, channel: Channel, sound: Sound);
+	
+	// Starts as soon as the clip has loaded, and keeps going until stopped
+	fn play_loop(&mut self, clip: ClipId, channel: Channel, sound: Sound) -> TrackId;
+	
+	// Changes a playing track, such as to follow the player around
+	fn set_track(&mut self, track: TrackId, sound: Sound);
+	
+	fn stop(&mut self, track: TrackId);
+	
+	fn set_master_volume(&mut self, volume: f64);
+	
+	fn set_channel_volume(&mut self, channel: Channel, volume: f64);
+	
+	// Clips that have failed to load since the last call
+	fn take_errors(&mut self) -> Vec<AssetError>;
+}
@@ -1,6 +1,8 @@
 use lib::Colour;
 use sized_matrix::Vector;
 
+use super::{Font, TextAlign, TextBaseline, TextMetrics};
+
 pub trait Image {
 	fn size(&self) -> Vector<u32, 2>;
 }
@@ -12,8 +14,22 @@ pub trait Canvas: Image {
 	
 	fn set_smoothing_quality(&mut self, quality: SmoothingQuality);
 	
+	// Screen pixels per CSS pixel, so things can be drawn the same physical size on any screen
+	fn pixel_ratio(&self) -> f64;
+	
+	// The new size if the canvas has been resized since the last call, which clears it
+	fn take_resize(&mut self) -> Option<Vector<u32, 2>>;
+	
 	fn fill_rect(&mut self, colour: Colour, pos: Vector<f64, 2>, size: Vector<f64, 2>);
 	
+	// Text settings stay until changed, like the browser's 2d context
+	fn set_font(&mut self, font: &Font);
+	fn set_text_align(&mut self, align: TextAlign);
+	fn set_text_baseline(&mut self, baseline: TextBaseline);
+	
+	fn fill_text(&mut self, colour: Colour, text: &str, pos: Vector<f64, 2>);
+	fn measure_text(&self, text: &str) -> TextMetrics;
+	
 	fn draw_image(&mut self, image: Self::TImage<'_>, pos: Vector<f64, 2>);
 	fn draw_image_scaled(&mut self, image: Self::TImage<'_>, pos: Vector<f64, 2>, size: Vector<f64, 2>);
 	fn draw_image_segment_scaled(&mut self, image: Self::TImage<'_>, source_pos: Vector<f64, 2>, source_size: Vector<f64, 2>, dest_pos: Vector<f64, 2>, dest_size: Vector<f64, 2>);
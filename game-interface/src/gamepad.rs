@@ -0,0 +1,170 @@
+use sized_matrix::Vector;
+
+use super::Keys;
+
+// Buttons in the order of the standard gamepad mapping
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum GamepadButton {
+	South,
+	East,
+	West,
+	North,
+	LeftBumper,
+	RightBumper,
+	LeftTrigger,
+	RightTrigger,
+	Select,
+	Start,
+	LeftStick,
+	RightStick,
+	DPadUp,
+	DPadDown,
+	DPadLeft,
+	DPadRight,
+	Home,
+}
+
+impl GamepadButton {
+	pub const ALL: [GamepadButton; 17] = [
+		GamepadButton::South,
+		GamepadButton::East,
+		GamepadButton::West,
+		GamepadButton::North,
+		GamepadButton::LeftBumper,
+		GamepadButton::RightBumper,
+		GamepadButton::LeftTrigger,
+		GamepadButton::RightTrigger,
+		GamepadButton::Select,
+		GamepadButton::Start,
+		GamepadButton::LeftStick,
+		GamepadButton::RightStick,
+		GamepadButton::DPadUp,
+		GamepadButton::DPadDown,
+		GamepadButton::DPadLeft,
+		GamepadButton::DPadRight,
+		GamepadButton::Home,
+	];
+	
+	// The name used for the button in input map bindings
+	pub fn code(&self) -> &'static str {
+		match self {
+			GamepadButton::South => "GamepadSouth",
+			GamepadButton::East => "GamepadEast",
+			GamepadButton::West => "GamepadWest",
+			GamepadButton::North => "GamepadNorth",
+			GamepadButton::LeftBumper => "GamepadLeftBumper",
+			GamepadButton::RightBumper => "GamepadRightBumper",
+			GamepadButton::LeftTrigger => "GamepadLeftTrigger",
+			GamepadButton::RightTrigger => "GamepadRightTrigger",
+			GamepadButton::Select => "GamepadSelect",
+			GamepadButton::Start => "GamepadStart",
+			GamepadButton::LeftStick => "GamepadLeftStick",
+			GamepadButton::RightStick => "GamepadRightStick",
+			GamepadButton::DPadUp => "GamepadDPadUp",
+			GamepadButton::DPadDown => "GamepadDPadDown",
+			GamepadButton::DPadLeft => "GamepadDPadLeft",
+			GamepadButton::DPadRight => "GamepadDPadRight",
+			GamepadButton::Home => "GamepadHome",
+		}
+	}
+	
+	pub fn from_code(code: &str) -> Option<Self> {
+		Self::ALL.iter().copied().find(|button| button.code() == code)
+	}
+}
+
+// Axes in the order of the standard gamepad mapping, with y positive downwards
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum GamepadAxis {
+	LeftX,
+	LeftY,
+	RightX,
+	RightY,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GamepadEvent {
+	Connected(u32),
+	Disconnected(u32),
+}
+
+// All connected gamepads combined, as if they were one
+pub trait Gamepad {
+	fn start(&self);
+	
+	// Reads the latest state, call once per frame
+	fn poll(&mut self);
+	
+	fn is_connected(&self) -> bool;
+	
+	// Raw value from -1 to 1, from whichever gamepad is pushed furthest
+	fn axis(&self, axis: GamepadAxis) -> f64;
+	
+	// From 0 to 1, since triggers are analogue
+	fn button(&self, button: GamepadButton) -> f64;
+	
+	fn is_pressed(&self, button: GamepadButton) -> bool {
+		self.button(button) >= Self::PRESS_THRESHOLD
+	}
+	
+	fn take_events(&mut self) -> Vec<GamepadEvent>;
+	
+	const PRESS_THRESHOLD: f64 = 0.5;
+}
+
+// Radial dead zone, so sticks that don't rest at the centre don't move the player, rescaled so movement still starts from zero
+pub struct DeadZone {
+	inner: f64,
+	outer: f64,
+}
+
+impl DeadZone {
+	pub fn new(inner: f64, outer: f64) -> Self {
+		Self {
+			inner,
+			outer,
+		}
+	}
+	
+	pub fn apply(&self, stick: Vector<f64, 2>) -> Vector<f64, 2> {
+		let magnitude = (stick[0] * stick[0] + stick[1] * stick[1]).sqrt();
+		if magnitude <= self.inner {
+			Vector::vector([0., 0.])
+		} else {
+			let scaled = ((magnitude - self.inner) / (self.outer - self.inner)).min(1.);
+			stick * (scaled / magnitude)
+		}
+	}
+	
+	pub fn stick<TGamepad: Gamepad>(&self, gamepad: &TGamepad, x: GamepadAxis, y: GamepadAxis) -> Vector<f64, 2> {
+		self.apply(Vector::vector([gamepad.axis(x), gamepad.axis(y)]))
+	}
+}
+
+// Lets gamepad buttons be bound in an InputMap by their codes
+pub struct KeysWithGamepad<'a, TKeys: Keys, TGamepad: Gamepad> {
+	keys: &'a TKeys,
+	gamepad: &'a TGamepad,
+}
+
+impl<'a, TKeys: Keys, TGamepad: Gamepad> KeysWithGamepad<'a, TKeys, TGamepad> {
+	pub fn new(keys: &'a TKeys, gamepad: &'a TGamepad) -> Self {
+		Self {
+			keys,
+			gamepad,
+		}
+	}
+}
+
+impl<TKeys: Keys, TGamepad: Gamepad> Keys for KeysWithGamepad<'_, TKeys, TGamepad> {
+	fn start(&self) {
+		self.keys.start();
+	}
+	
+	fn is_pressed(&self, key: &str) -> bool {
+		match GamepadButton::from_code(key) {
+			Some(button) => self.gamepad.is_pressed(button),
+			None => self.keys.is_pressed(key),
+		}
+	}
+}
@@ -0,0 +1,208 @@
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fmt,
+	str::FromStr,
+};
+
+use serde::{Serialize, Deserialize};
+
+use super::Keys;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub enum Action {
+	MoveLeft,
+	MoveRight,
+	MoveUp,
+	MoveDown,
+	Jump,
+	Dig,
+	Place,
+	Inventory,
+	Map,
+	ZoomIn,
+	ZoomOut,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Modifiers {
+	pub shift: bool,
+	pub ctrl: bool,
+	pub alt: bool,
+	pub meta: bool,
+}
+
+impl Modifiers {
+	pub const NONE: Self = Self { shift: false, ctrl: false, alt: false, meta: false };
+	
+	pub fn is_held<TKeys: Keys>(&self, keys: &TKeys) -> bool {
+		let either = |left: &str, right: &str| keys.is_pressed(left) || keys.is_pressed(right);
+		(!self.shift || either("ShiftLeft", "ShiftRight")) &&
+		(!self.ctrl || either("ControlLeft", "ControlRight")) &&
+		(!self.alt || either("AltLeft", "AltRight")) &&
+		(!self.meta || either("MetaLeft", "MetaRight"))
+	}
+	
+	// Whether every modifier in other is also in self
+	pub fn contains(&self, other: &Self) -> bool {
+		(self.shift || !other.shift) &&
+		(self.ctrl || !other.ctrl) &&
+		(self.alt || !other.alt) &&
+		(self.meta || !other.meta)
+	}
+}
+
+// A physical key code, as in KeyboardEvent.code, or a GamepadButton code, plus the modifiers that must be held with it
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Binding {
+	pub code: String,
+	#[serde(default)]
+	pub modifiers: Modifiers,
+}
+
+impl Binding {
+	pub fn key(code: &str) -> Self {
+		Self::with_modifiers(code, Modifiers::NONE)
+	}
+	
+	pub fn with_modifiers(code: &str, modifiers: Modifiers) -> Self {
+		Self {
+			code: String::from(code),
+			modifiers,
+		}
+	}
+	
+	pub fn is_held<TKeys: Keys>(&self, keys: &TKeys) -> bool {
+		keys.is_pressed(&self.code) && self.modifiers.is_held(keys)
+	}
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct InputMap {
+	bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl InputMap {
+	pub fn new() -> Self {
+		Self {
+			bindings: BTreeMap::new(),
+		}
+	}
+	
+	pub fn standard() -> Self {
+		let mut map = Self::new();
+		for (action, codes) in [
+			(Action::MoveLeft, &["KeyA", "ArrowLeft", "GamepadDPadLeft"][..]),
+			(Action::MoveRight, &["KeyD", "ArrowRight", "GamepadDPadRight"]),
+			(Action::MoveUp, &["KeyW", "ArrowUp", "GamepadDPadUp"]),
+			(Action::MoveDown, &["KeyS", "ArrowDown", "GamepadDPadDown"]),
+			(Action::Jump, &["Space", "GamepadSouth"]),
+			(Action::Dig, &["KeyE", "GamepadRightTrigger"]),
+			(Action::Place, &["KeyQ", "GamepadLeftTrigger"]),
+			(Action::Inventory, &["KeyI", "Tab", "GamepadNorth"]),
+			(Action::Map, &["KeyM", "GamepadSelect"]),
+			(Action::ZoomIn, &["Equal", "NumpadAdd", "GamepadRightBumper"]),
+			(Action::ZoomOut, &["Minus", "NumpadSubtract", "GamepadLeftBumper"]),
+		].iter() {
+			for code in codes.iter() {
+				map.bind(*action, Binding::key(code));
+			}
+		}
+		map
+	}
+	
+	pub fn bind(&mut self, action: Action, binding: Binding) {
+		let bindings = self.bindings.entry(action).or_default();
+		if !bindings.contains(&binding) {
+			bindings.push(binding);
+		}
+	}
+	
+	pub fn unbind(&mut self, action: Action, binding: &Binding) -> bool {
+		match self.bindings.get_mut(&action) {
+			Some(bindings) => {
+				let len = bindings.len();
+				bindings.retain(|other| other != binding);
+				bindings.len() != len
+			},
+			None => false,
+		}
+	}
+	
+	pub fn clear(&mut self, action: Action) {
+		self.bindings.remove(&action);
+	}
+	
+	pub fn bindings(&self, action: Action) -> &[Binding] {
+		self.bindings.get(&action).map_or(&[], |bindings| bindings.as_slice())
+	}
+	
+	// A binding is ignored while a more specific binding for the same key is held, so Shift+E can mean something other than E
+	pub fn is_active<TKeys: Keys>(&self, action: Action, keys: &TKeys) -> bool {
+		self.bindings(action).iter().any(|binding| {
+			binding.is_held(keys) && !self.bindings.values().flatten().any(|other|
+				other.code == binding.code &&
+				other.modifiers != binding.modifiers &&
+				other.modifiers.contains(&binding.modifiers) &&
+				other.modifiers.is_held(keys)
+			)
+		})
+	}
+	
+	pub fn to_ron(&self) -> String {
+		ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).expect("Input maps should always serialise")
+	}
+}
+
+impl FromStr for InputMap {
+	type Err = InputMapError;
+	
+	fn from_str(text: &str) -> Result<Self, InputMapError> {
+		ron::from_str(text).map_err(|err| InputMapError(err.to_string()))
+	}
+}
+
+#[derive(Debug)]
+pub struct InputMapError(String);
+
+impl fmt::Display for InputMapError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Could not parse input map: {}", self.0)
+	}
+}
+
+impl std::error::Error for InputMapError { }
+
+// Which actions are held, and which started being held on the last update
+pub struct ActionState {
+	held: BTreeSet<Action>,
+	pressed: BTreeSet<Action>,
+}
+
+impl ActionState {
+	pub fn new() -> Self {
+		Self {
+			held: BTreeSet::new(),
+			pressed: BTreeSet::new(),
+		}
+	}
+	
+	pub fn update<TKeys: Keys>(&mut self, map: &InputMap, keys: &TKeys) {
+		let held: BTreeSet<Action> = map.bindings.keys().copied().filter(|action| map.is_active(*action, keys)).collect();
+		self.pressed = held.difference(&self.held).copied().collect();
+		self.held = held;
+	}
+	
+	pub fn is_held(&self, action: Action) -> bool {
+		self.held.contains(&action)
+	}
+	
+	pub fn was_pressed(&self, action: Action) -> bool {
+		self.pressed.contains(&action)
+	}
+	
+	// -1, 0 or 1 depending on which of the two actions are held
+	pub fn axis(&self, negative: Action, positive: Action) -> f64 {
+		(if self.is_held(positive) { 1. } else { 0. }) - (if self.is_held(negative) { 1. } else { 0. })
+	}
+}
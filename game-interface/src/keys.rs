@@ -1,3 +1,4 @@
+// Keys are identified by physical key code, as in KeyboardEvent.code, such as "KeyW", "ArrowUp" or "ShiftLeft"
 pub trait Keys {
 	fn start(&self);
 	
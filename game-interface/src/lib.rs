@@ -1,10 +1,115 @@
 #![feature(generic_associated_types)]
 
+mod assets;
+pub use assets::*;
+
+mod audio;
+pub use audio::*;
+
 mod canvas;
 pub use canvas::*;
 
+mod gamepad;
+pub use gamepad::*;
+
+mod input_map;
+pub use input_map::*;
+
 mod keys;
 pub use keys::*;
 
+mod pointer;
+pub use pointer::*;
+
+mod sprite_atlas;
+pub use sprite_atlas::*;
+
+mod storage;
+pub use storage::*;
+
+mod text;
+pub use text::*;
+
 mod timer;
 pub use timer::*;
+
+mod touch;
+pub use touch::*;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	use std::{collections::HashSet, str::FromStr};
+	
+	struct Pressed(HashSet<&'static str>);
+	
+	impl Keys for Pressed {
+		fn start(&self) { }
+		
+		fn is_pressed(&self, key: &str) -> bool {
+			self.0.contains(key)
+		}
+	}
+	
+	fn pressed(keys: &[&'static str]) -> Pressed {
+		Pressed(keys.iter().copied().collect())
+	}
+	
+	#[test]
+	fn modified_bindings_take_priority() {
+		let mut map = InputMap::standard();
+		map.bind(Action::Inventory, Binding::with_modifiers("KeyE", Modifiers { shift: true, ..Modifiers::NONE }));
+		
+		assert!(map.is_active(Action::Dig, &pressed(&["KeyE"])));
+		assert!(!map.is_active(Action::Inventory, &pressed(&["KeyE"])));
+		assert!(!map.is_active(Action::Dig, &pressed(&["KeyE", "ShiftLeft"])));
+		assert!(map.is_active(Action::Inventory, &pressed(&["KeyE", "ShiftLeft"])));
+		// Modifiers don't stop unrelated bindings, so running with Shift held still works
+		assert!(map.is_active(Action::MoveRight, &pressed(&["KeyD", "ShiftRight"])));
+	}
+	
+	#[test]
+	fn input_map_round_trips() {
+		let mut map = InputMap::standard();
+		map.clear(Action::Jump);
+		map.bind(Action::Jump, Binding::with_modifiers("KeyZ", Modifiers { ctrl: true, ..Modifiers::NONE }));
+		
+		assert_eq!(InputMap::from_str(&map.to_ron()).unwrap(), map);
+		assert_eq!(InputMap::from_str("(bindings: { Dig: [(code: \"KeyF\")] })").unwrap().bindings(Action::Dig), &[Binding::key("KeyF")]);
+	}
+	
+	#[test]
+	fn actions_report_presses_once() {
+		let map = InputMap::standard();
+		let mut actions = ActionState::new();
+		
+		actions.update(&map, &pressed(&["Space", "KeyA"]));
+		assert!(actions.was_pressed(Action::Jump));
+		assert_eq!(actions.axis(Action::MoveLeft, Action::MoveRight), -1.);
+		
+		actions.update(&map, &pressed(&["Space"]));
+		assert!(actions.is_held(Action::Jump));
+		assert!(!actions.was_pressed(Action::Jump));
+	}
+	
+	#[test]
+	fn sprite_atlas_names_grid_cells() {
+		let atlas = SpriteAtlas::grid("blocks.png", 2, sized_matrix::Vector::vector([8, 8]), &[Some("stone"), None, Some("dirt")]);
+		
+		assert_eq!(atlas.get("dirt"), Some(&Sprite { x: 0, y: 8, width: 8, height: 8 }));
+		assert_eq!(atlas.names().collect::<Vec<&str>>(), vec!["dirt", "stone"]);
+		assert_eq!(SpriteAtlas::from_str(&atlas.to_ron()).unwrap(), atlas);
+		assert!(SpriteAtlas::from_str("(image: \"blocks.png\")").is_err());
+	}
+	
+	#[test]
+	fn sounds_fade_and_pan_with_distance() {
+		let near = Sound::at(sized_matrix::Vector::vector([-2., 0.]), 20.);
+		let far = Sound::at(sized_matrix::Vector::vector([0., 30.]), 20.);
+		
+		assert!((near.volume - 0.9).abs() < 1e-9 && (near.pan + 0.1).abs() < 1e-9);
+		assert_eq!(far.volume, 0.);
+		assert_eq!(far.pitch, 1.);
+	}
+}
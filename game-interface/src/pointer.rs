@@ -0,0 +1,24 @@
+use sized_matrix::Vector;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PointerButton {
+	Primary,
+	Secondary,
+	Middle,
+}
+
+pub trait Pointer {
+	fn start(&self);
+	
+	// Position in canvas pixels, or None while the pointer is outside the canvas
+	fn position(&self) -> Option<Vector<f64, 2>>;
+	
+	fn is_inside(&self) -> bool {
+		self.position().is_some()
+	}
+	
+	fn is_pressed(&self, button: PointerButton) -> bool;
+	
+	// Wheel movement in lines since the last call, positive when scrolling down
+	fn take_wheel(&mut self) -> f64;
+}
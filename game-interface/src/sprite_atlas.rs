@@ -0,0 +1,110 @@
+use std::{
+	collections::BTreeMap,
+	fmt,
+	str::FromStr,
+};
+
+use serde::{Serialize, Deserialize};
+
+use sized_matrix::Vector;
+
+use super::{Assets, Canvas, ImageId};
+
+// A rectangle of the atlas image, in image pixels
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Sprite {
+	pub x: u32,
+	pub y: u32,
+	pub width: u32,
+	pub height: u32,
+}
+
+impl Sprite {
+	pub fn pos(&self) -> Vector<f64, 2> {
+		Vector::vector([self.x as f64, self.y as f64])
+	}
+	
+	pub fn size(&self) -> Vector<f64, 2> {
+		Vector::vector([self.width as f64, self.height as f64])
+	}
+}
+
+// Names for the sprites packed into one image, stored as RON next to the image
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SpriteAtlas {
+	// Path of the image, as passed to Assets::load_image
+	pub image: String,
+	sprites: BTreeMap<String, Sprite>,
+}
+
+impl SpriteAtlas {
+	pub fn new(image: &str) -> Self {
+		Self {
+			image: String::from(image),
+			sprites: BTreeMap::new(),
+		}
+	}
+	
+	// Names a grid of equally sized cells, left to right then top to bottom, with None for unused cells
+	pub fn grid(image: &str, columns: u32, cell: Vector<u32, 2>, names: &[Option<&str>]) -> Self {
+		let mut atlas = Self::new(image);
+		for (index, name) in names.iter().enumerate() {
+			if let Some(name) = name {
+				let index = index as u32;
+				atlas.insert(name, Sprite {
+					x: index % columns * cell[0],
+					y: index / columns * cell[1],
+					width: cell[0],
+					height: cell[1],
+				});
+			}
+		}
+		atlas
+	}
+	
+	pub fn insert(&mut self, name: &str, sprite: Sprite) {
+		self.sprites.insert(String::from(name), sprite);
+	}
+	
+	pub fn get(&self, name: &str) -> Option<&Sprite> {
+		self.sprites.get(name)
+	}
+	
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		self.sprites.keys().map(String::as_str)
+	}
+	
+	// Returns false without drawing if the sprite doesn't exist or the image hasn't loaded
+	pub fn draw<TCanvas: Canvas, TAssets: Assets<TCanvas>>(&self, canvas: &mut TCanvas, assets: &TAssets, image: ImageId, name: &str, pos: Vector<f64, 2>, size: Vector<f64, 2>) -> bool {
+		match (self.get(name), assets.image(image)) {
+			(Some(sprite), Some(image)) => {
+				canvas.draw_image_segment_scaled(image, sprite.pos(), sprite.size(), pos, size);
+				true
+			},
+			_ => false,
+		}
+	}
+	
+	pub fn to_ron(&self) -> String {
+		ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).expect("Sprite atlases should always serialise")
+	}
+}
+
+impl FromStr for SpriteAtlas {
+	type Err = SpriteAtlasError;
+	
+	fn from_str(text: &str) -> Result<Self, SpriteAtlasError> {
+		ron::from_str(text).map_err(|err| SpriteAtlasError(err.to_string()))
+	}
+}
+
+#[derive(Debug)]
+pub struct SpriteAtlasError(String);
+
+impl fmt::Display for SpriteAtlasError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Could not parse sprite atlas: {}", self.0)
+	}
+}
+
+impl std::error::Error for SpriteAtlasError { }
@@ -0,0 +1,27 @@
+use std::{
+	cell::RefCell,
+	rc::Rc,
+};
+
+pub trait Storage {
+	fn load(&self, key: &str) -> Option<Vec<u8>>;
+	
+	fn store(&mut self, key: &str, data: &[u8]) -> Result<(), String>;
+	
+	fn remove(&mut self, key: &str);
+}
+
+// Lets several parts of the game share one storage
+impl<TStorage: Storage> Storage for Rc<RefCell<TStorage>> {
+	fn load(&self, key: &str) -> Option<Vec<u8>> {
+		self.borrow().load(key)
+	}
+	
+	fn store(&mut self, key: &str, data: &[u8]) -> Result<(), String> {
+		self.borrow_mut().store(key, data)
+	}
+	
+	fn remove(&mut self, key: &str) {
+		self.borrow_mut().remove(key)
+	}
+}
@@ -0,0 +1,45 @@
+#[derive(Clone, PartialEq, Debug)]
+pub struct Font {
+	// A CSS font family, backends without that font use their own
+	pub family: String,
+	// Height in pixels
+	pub size: f64,
+}
+
+impl Font {
+	pub fn new(family: &str, size: f64) -> Self {
+		Self {
+			family: String::from(family),
+			size,
+		}
+	}
+	
+	pub fn to_css(&self) -> String {
+		format!("{}px {}", self.size, self.family)
+	}
+}
+
+// Which part of the text is drawn at the x position
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TextAlign {
+	Left,
+	Centre,
+	Right,
+}
+
+// Which part of the text is drawn at the y position
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TextBaseline {
+	Top,
+	Middle,
+	Alphabetic,
+	Bottom,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TextMetrics {
+	pub width: f64,
+	// Distances above and below the alphabetic baseline for the font as a whole, so they don't change with the text
+	pub ascent: f64,
+	pub descent: f64,
+}
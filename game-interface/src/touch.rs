@@ -0,0 +1,16 @@
+use sized_matrix::Vector;
+
+#[derive(Copy, Clone, PartialEq)]
+pub struct TouchPoint {
+	// Stays the same while the finger is down
+	pub id: u32,
+	// Position in canvas pixels
+	pub pos: Vector<f64, 2>,
+}
+
+pub trait Touch {
+	fn start(&self);
+	
+	// Every finger currently touching the canvas
+	fn touches(&self) -> &[TouchPoint];
+}
@@ -0,0 +1,111 @@
+use std::{
+	io::ErrorKind,
+	net::TcpStream,
+	sync::mpsc::{
+		self,
+		Sender,
+		Receiver,
+		TryRecvError,
+	},
+	time::Duration,
+};
+
+use serde::{
+	Serialize,
+	de::DeserializeOwned,
+};
+
+use tungstenite::{
+	Error,
+	Message,
+	WebSocket,
+};
+
+use lib::Logger;
+
+pub enum Event<TIn, TOut> {
+	Connected { id: u32, sender: Sender<TOut> },
+	Message { id: u32, message: TIn },
+	Disconnected { id: u32 },
+	// The process is stopping, so anything unsaved should be saved now
+	Shutdown,
+}
+
+// Each connection runs on its own thread, forwarding decoded JSON messages to the thread that
+// owns the state and sending anything that thread queues for it
+pub struct Connection<TIn, TOut> {
+	id: u32,
+	socket: WebSocket<TcpStream>,
+	events: Sender<Event<TIn, TOut>>,
+	outgoing: Receiver<TOut>,
+}
+
+impl<TIn: DeserializeOwned, TOut: Serialize> Connection<TIn, TOut> {
+	pub fn run(id: u32, stream: TcpStream, events: Sender<Event<TIn, TOut>>) {
+		let socket = match tungstenite::accept(stream) {
+			Ok(socket) => socket,
+			Err(err) => {
+				Logger::warning(&format!("Handshake with client {} failed: {}", id, err));
+				return;
+			},
+		};
+		
+		// Poll so that outgoing messages aren't stuck behind a blocking read
+		if let Err(err) = socket.get_ref().set_read_timeout(Some(Self::POLL_INTERVAL)) {
+			Logger::warning(&format!("Failed to configure client {}: {}", id, err));
+			return;
+		}
+		
+		let (sender, outgoing) = mpsc::channel();
+		if events.send(Event::Connected { id, sender }).is_err() {
+			return;
+		}
+		
+		let mut connection = Self {
+			id,
+			socket,
+			events,
+			outgoing,
+		};
+		
+		if let Err(err) = connection.serve() {
+			Logger::info(&format!("Client {} disconnected: {}", id, err));
+		}
+		
+		let _ = connection.events.send(Event::Disconnected { id });
+	}
+	
+	fn serve(&mut self) -> Result<(), String> {
+		loop {
+			loop {
+				match self.outgoing.try_recv() {
+					Ok(message) => {
+						let text = serde_json::to_string(&message).map_err(|err| err.to_string())?;
+						self.socket.write_message(Message::Text(text)).map_err(|err| err.to_string())?;
+					},
+					Err(TryRecvError::Empty) => break,
+					Err(TryRecvError::Disconnected) => return Err(String::from("Server closed the connection")),
+				}
+			}
+			
+			match self.socket.read_message() {
+				Ok(Message::Text(text)) => match serde_json::from_str(&text) {
+					Ok(message) => {
+						self.events.send(Event::Message { id: self.id, message })
+							.map_err(|_| String::from("Server stopped"))?;
+					},
+					Err(err) => Logger::warning(&format!("Invalid message from client {}: {}", self.id, err)),
+				},
+				Ok(Message::Close(_)) => return Ok(()),
+				Ok(_) => (),
+				Err(Error::Io(err)) if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut => {
+					self.socket.write_pending().map_err(|err| err.to_string())?;
+				},
+				Err(Error::ConnectionClosed) => return Ok(()),
+				Err(err) => return Err(err.to_string()),
+			}
+		}
+	}
+	
+	const POLL_INTERVAL: Duration = Duration::from_millis(20);
+}
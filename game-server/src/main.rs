@@ -1,3 +1,127 @@
+mod connection;
+use connection::*;
+
+mod server;
+use server::*;
+
+mod signalling;
+use signalling::*;
+
+use std::{
+	env,
+	net::TcpListener,
+	sync::mpsc::{
+		self,
+		Sender,
+	},
+	thread,
+};
+
+use serde::{
+	Serialize,
+	de::DeserializeOwned,
+};
+
+use game_state::{
+	GameRules,
+	RegionStorage,
+	World,
+};
+
+use lib::Logger;
+
+struct Options {
+	address: String,
+	signal_address: String,
+	world: String,
+	rules: Option<String>,
+	seed: u64,
+}
+
+impl Options {
+	fn parse() -> Result<Self, String> {
+		let mut options = Self {
+			address: String::from("127.0.0.1:9001"),
+			signal_address: String::from("127.0.0.1:9002"),
+			world: String::from("world"),
+			rules: None,
+			seed: 123,
+		};
+		
+		let mut args = env::args().skip(1);
+		while let Some(arg) = args.next() {
+			let mut value = || args.next().ok_or_else(|| format!("Missing value for {}", arg));
+			match arg.as_str() {
+				"--address" => options.address = value()?,
+				"--signal-address" => options.signal_address = value()?,
+				"--world" => options.world = value()?,
+				"--rules" => options.rules = Some(value()?),
+				"--seed" => options.seed = value()?.parse().map_err(|_| String::from("Seed must be a number"))?,
+				_ => return Err(format!("Unknown argument {}", arg)),
+			}
+		}
+		
+		Ok(options)
+	}
+}
+
 fn main() {
-    println!("Hello, world!");
+	let options = match Options::parse() {
+		Ok(options) => options,
+		Err(err) => {
+			eprintln!("{}", err);
+			eprintln!("Usage: game-server [--address <host:port>] [--signal-address <host:port>] [--world <directory>] [--rules <file>] [--seed <number>]");
+			std::process::exit(2);
+		},
+	};
+	
+	let rules = match &options.rules {
+		Some(path) => GameRules::load_file(path).unwrap_or_else(|err| {
+			eprintln!("{}", err);
+			std::process::exit(1);
+		}),
+		None => GameRules::load(),
+	};
+	let rules: &'static GameRules = Box::leak(Box::new(rules));
+	
+	let storage = RegionStorage::new(&options.world).unwrap_or_else(|err| {
+		eprintln!("Could not open world directory {}: {}", options.world, err);
+		std::process::exit(1);
+	});
+	let world = World::with_storage(rules, options.seed, Box::new(storage));
+	
+	let (signal_events, signal_receiver) = mpsc::channel();
+	listen(&options.signal_address, signal_events);
+	thread::spawn(move || run_signalling(signal_receiver));
+	
+	let (events, receiver) = mpsc::channel();
+	// The listener never stops sending events, so stopping is its own event, letting the world be saved first
+	let shutdown = events.clone();
+	if let Err(err) = ctrlc::set_handler(move || {
+		let _ = shutdown.send(Event::Shutdown);
+	}) {
+		Logger::warning(&format!("Failed to handle Ctrl-C, unsaved changes will be lost when stopped: {}", err));
+	}
+	listen(&options.address, events);
+	Server::new(world, options.seed).run(receiver);
+}
+
+fn listen<TIn: 'static + DeserializeOwned + Send, TOut: 'static + Serialize + Send>(address: &str, events: Sender<Event<TIn, TOut>>) {
+	let listener = TcpListener::bind(address).unwrap_or_else(|err| {
+		eprintln!("Could not listen on {}: {}", address, err);
+		std::process::exit(1);
+	});
+	println!("Listening on ws://{}", address);
+	
+	thread::spawn(move || {
+		for (id, stream) in (0..).zip(listener.incoming()) {
+			match stream {
+				Ok(stream) => {
+					let events = events.clone();
+					thread::spawn(move || Connection::run(id, stream, events));
+				},
+				Err(err) => Logger::warning(&format!("Failed to accept connection: {}", err)),
+			}
+		}
+	});
 }
@@ -0,0 +1,291 @@
+use super::*;
+
+use std::{
+	collections::HashMap,
+	sync::mpsc::{
+		Receiver,
+		RecvTimeoutError,
+		Sender,
+	},
+	time::{
+		Duration,
+		Instant,
+	},
+};
+
+use game_state::{
+	Chunk,
+	ClientMessage,
+	Player,
+	ServerMessage,
+};
+
+use sized_matrix::Vector;
+
+struct Client {
+	name: Option<String>,
+	// The last position accepted from the client, and when it was accepted
+	pos: Vector<f64, 2>,
+	moved_at: Instant,
+	// Chunk requests the client can make straight away, refilled over time
+	chunk_requests: f64,
+	requested_at: Instant,
+	sender: Sender<ServerMessage>,
+}
+
+pub struct Server {
+	world: World,
+	seed: u64,
+	spawn: Vector<f64, 2>,
+	clients: HashMap<u32, Client>,
+}
+
+impl Server {
+	pub fn new(mut world: World, seed: u64) -> Self {
+		let spawn = Player::spawn(&mut world, 0).pos();
+		Self {
+			world,
+			seed,
+			spawn,
+			clients: HashMap::new(),
+		}
+	}
+	
+	pub fn run(mut self, events: Receiver<Event<ClientMessage, ServerMessage>>) {
+		let mut last_save = Instant::now();
+		loop {
+			match events.recv_timeout(Self::SAVE_INTERVAL) {
+				Ok(Event::Shutdown) => break,
+				Ok(event) => self.handle(event),
+				Err(RecvTimeoutError::Timeout) => (),
+				Err(RecvTimeoutError::Disconnected) => break,
+			}
+			if last_save.elapsed() >= Self::SAVE_INTERVAL {
+				self.save();
+				last_save = Instant::now();
+			}
+		}
+		Logger::info("Stopping");
+		self.save();
+	}
+	
+	fn save(&mut self) {
+		match self.world.save() {
+			Ok(0) => (),
+			Ok(count) => Logger::debug(&format!("Saved {} chunks", count)),
+			Err(err) => Logger::error(&format!("Failed to save world: {}", err)),
+		}
+	}
+	
+	fn handle(&mut self, event: Event<ClientMessage, ServerMessage>) {
+		match event {
+			Event::Connected { id, sender } => {
+				let _ = sender.send(ServerMessage::Welcome {
+					id,
+					seed: self.seed,
+					palette: self.world.rules().blocks().palette(),
+					spawn: [self.spawn[0], self.spawn[1]],
+				});
+				for (&other, client) in &self.clients {
+					if let Some(name) = &client.name {
+						let _ = sender.send(ServerMessage::PlayerJoined { id: other, name: name.clone() });
+						let _ = sender.send(ServerMessage::PlayerMoved { id: other, pos: [client.pos[0], client.pos[1]] });
+					}
+				}
+				self.clients.insert(id, Client {
+					name: None,
+					pos: self.spawn,
+					moved_at: Instant::now(),
+					chunk_requests: Self::MAX_CHUNK_REQUESTS,
+					requested_at: Instant::now(),
+					sender,
+				});
+				self.send_area(id, None, chunk_of(self.spawn));
+			},
+			Event::Message { id, message } => self.handle_message(id, message),
+			Event::Disconnected { id } => {
+				if let Some(client) = self.clients.remove(&id) {
+					if client.name.is_some() {
+						self.broadcast(ServerMessage::PlayerLeft { id }, Some(id));
+					}
+				}
+			},
+			Event::Shutdown => (),
+		}
+	}
+	
+	fn handle_message(&mut self, id: u32, message: ClientMessage) {
+		match message {
+			ClientMessage::Hello { name } => {
+				let name: String = name.chars().filter(|c| !c.is_control()).take(Self::MAX_NAME_LENGTH).collect();
+				if let Some(client) = self.clients.get_mut(&id) {
+					client.name = Some(name.clone());
+				}
+				self.broadcast(ServerMessage::PlayerJoined { id, name }, Some(id));
+			},
+			ClientMessage::RequestChunk { chunk } => {
+				match self.validate_chunk_request(id, Vector::vector(chunk)) {
+					Ok(()) => {
+						let changes = self.world.chunk_diff(Vector::vector(chunk));
+						self.send(id, ServerMessage::ChunkDiff { chunk, changes });
+					},
+					Err(reason) => self.send(id, ServerMessage::ChunkRejected { chunk, reason: String::from(reason) }),
+				}
+			},
+			ClientMessage::Position { pos } => {
+				let max_speed = self.world.rules().physics().max_speed();
+				let client = match self.clients.get_mut(&id) {
+					Some(client) => client,
+					None => return,
+				};
+				// Time spent standing still doesn't build up, so it can't be saved for a long jump later. Positions that aren't finite fail the comparison.
+				let elapsed = client.moved_at.elapsed().as_secs_f64().min(Self::MAX_MOVE_TIME);
+				let distance = Vector::vector(pos) - client.pos;
+				let max_distance = max_speed * elapsed + Self::MOVE_TOLERANCE;
+				if distance[0] * distance[0] + distance[1] * distance[1] <= max_distance * max_distance {
+					let old_area = chunk_of(client.pos);
+					client.pos = Vector::vector(pos);
+					client.moved_at = Instant::now();
+					self.broadcast(ServerMessage::PlayerMoved { id, pos }, Some(id));
+					let area = chunk_of(Vector::vector(pos));
+					if area != old_area {
+						self.send_area(id, Some(old_area), area);
+					}
+				} else {
+					let pos = [client.pos[0], client.pos[1]];
+					self.send(id, ServerMessage::MoveRejected { pos });
+				}
+			},
+			ClientMessage::Dig { pos } => {
+				match self.validate_dig(id, Vector::vector(pos)) {
+					Ok(()) => {
+						self.world.dig(Vector::vector(pos));
+						self.broadcast_changes();
+					},
+					Err(reason) => self.send(id, ServerMessage::EditRejected { pos, reason: String::from(reason) }),
+				}
+			},
+			ClientMessage::Place { pos, block } => {
+				match self.validate_place(id, Vector::vector(pos), block) {
+					Ok(()) => {
+						self.world.set(Vector::vector(pos), block);
+						self.broadcast_changes();
+					},
+					Err(reason) => self.send(id, ServerMessage::EditRejected { pos, reason: String::from(reason) }),
+				}
+			},
+		}
+	}
+	
+	// Requests load or generate chunks, so they are limited to around the player and to a steady rate
+	fn validate_chunk_request(&mut self, id: u32, chunk: Vector<i32, 2>) -> Result<(), &'static str> {
+		let client = self.clients.get_mut(&id).ok_or("Not connected")?;
+		if !in_view(chunk_of(client.pos), chunk) {
+			return Err("Out of range");
+		}
+		let refill = client.requested_at.elapsed().as_secs_f64() * Self::CHUNK_REQUEST_RATE;
+		client.chunk_requests = (client.chunk_requests + refill).min(Self::MAX_CHUNK_REQUESTS);
+		client.requested_at = Instant::now();
+		if client.chunk_requests < 1. {
+			return Err("Too many requests");
+		}
+		client.chunk_requests -= 1.;
+		Ok(())
+	}
+	
+	// Sends the edited chunks that are in view around the area but weren't around the old one
+	// Unedited chunks are left for the client to generate from the seed
+	fn send_area(&mut self, id: u32, old_area: Option<Vector<i32, 2>>, area: Vector<i32, 2>) {
+		for y in -Self::VIEW_DISTANCE..=Self::VIEW_DISTANCE {
+			for x in -Self::VIEW_DISTANCE..=Self::VIEW_DISTANCE {
+				let chunk = area + Vector::vector([x, y]);
+				if old_area.map_or(false, |old_area| in_view(old_area, chunk)) {
+					continue;
+				}
+				let changes = self.world.chunk_diff(chunk);
+				if !changes.is_empty() {
+					self.send(id, ServerMessage::ChunkDiff { chunk: [chunk[0], chunk[1]], changes });
+				}
+			}
+		}
+	}
+	
+	fn validate_reach(&self, id: u32, pos: Vector<i32, 2>) -> Result<(), &'static str> {
+		let player = self.clients.get(&id).map(|client| client.pos).ok_or("Not connected")?;
+		let dx = pos[0] as f64 + 0.5 - player[0];
+		let dy = pos[1] as f64 + 0.5 - player[1];
+		if dx * dx + dy * dy > self.world.rules().reach().powi(2) {
+			return Err("Out of reach");
+		}
+		Ok(())
+	}
+	
+	fn validate_dig(&mut self, id: u32, pos: Vector<i32, 2>) -> Result<(), &'static str> {
+		self.validate_reach(id, pos)?;
+		if !self.world.get(pos).solid {
+			return Err("Nothing to dig");
+		}
+		Ok(())
+	}
+	
+	fn validate_place(&mut self, id: u32, pos: Vector<i32, 2>, block: u16) -> Result<(), &'static str> {
+		self.validate_reach(id, pos)?;
+		if block as usize >= self.world.rules().blocks().len() {
+			return Err("Unknown block");
+		}
+		if self.world.get(pos).solid {
+			return Err("Space is occupied");
+		}
+		if self.world.rules().block(block).solid && self.clients.values().any(|client| overlaps_player(client.pos, pos)) {
+			return Err("Space is occupied by a player");
+		}
+		Ok(())
+	}
+	
+	fn broadcast_changes(&mut self) {
+		for pos in self.world.take_changes() {
+			let block = self.world.get_id(pos);
+			self.broadcast(ServerMessage::BlockChanged { pos: [pos[0], pos[1]], block }, None);
+		}
+	}
+	
+	fn send(&self, id: u32, message: ServerMessage) {
+		if let Some(client) = self.clients.get(&id) {
+			let _ = client.sender.send(message);
+		}
+	}
+	
+	fn broadcast(&self, message: ServerMessage, except: Option<u32>) {
+		for (&id, client) in &self.clients {
+			if Some(id) != except {
+				let _ = client.sender.send(message.clone());
+			}
+		}
+	}
+	
+	const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+	const MAX_NAME_LENGTH: usize = 32;
+	// In seconds
+	const MAX_MOVE_TIME: f64 = 1.;
+	// In blocks, for messages that arrive bunched up
+	const MOVE_TOLERANCE: f64 = 2.;
+	// In chunks from the one the player is in
+	const VIEW_DISTANCE: i32 = 4;
+	// Per second, with bursts of up to a whole view
+	const CHUNK_REQUEST_RATE: f64 = 20.;
+	const MAX_CHUNK_REQUESTS: f64 = ((2 * Self::VIEW_DISTANCE + 1) * (2 * Self::VIEW_DISTANCE + 1)) as f64;
+}
+
+fn chunk_of(pos: Vector<f64, 2>) -> Vector<i32, 2> {
+	Vector::vector([(pos[0] / Chunk::SIZE as f64).floor() as i32, (pos[1] / Chunk::SIZE as f64).floor() as i32])
+}
+
+fn in_view(area: Vector<i32, 2>, chunk: Vector<i32, 2>) -> bool {
+	(chunk[0] - area[0]).abs() <= Server::VIEW_DISTANCE && (chunk[1] - area[1]).abs() <= Server::VIEW_DISTANCE
+}
+
+fn overlaps_player(player: Vector<f64, 2>, pos: Vector<i32, 2>) -> bool {
+	let (x, y) = (pos[0] as f64, pos[1] as f64);
+	player[0] - Player::WIDTH / 2. < x + 1. && player[0] + Player::WIDTH / 2. > x &&
+	player[1] - Player::HEIGHT / 2. < y + 1. && player[1] + Player::HEIGHT / 2. > y
+}
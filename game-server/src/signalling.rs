@@ -0,0 +1,39 @@
+use super::*;
+
+use std::{
+	collections::HashMap,
+	sync::mpsc::Receiver,
+};
+
+use peer_server::{
+	SignallingServer,
+	SignalRequest,
+	SignalResponse,
+};
+
+pub fn run_signalling(events: Receiver<Event<SignalRequest, SignalResponse>>) {
+	let mut server = SignallingServer::new();
+	let mut clients = HashMap::new();
+	
+	for event in events {
+		let messages = match event {
+			Event::Connected { id, sender } => {
+				clients.insert(id, sender);
+				server.connect(id);
+				Vec::new()
+			},
+			Event::Message { id, message } => server.handle(id, message),
+			Event::Disconnected { id } => {
+				clients.remove(&id);
+				server.disconnect(id)
+			},
+			Event::Shutdown => break,
+		};
+		
+		for (id, message) in messages {
+			if let Some(sender) = clients.get(&id) {
+				let _ = sender.send(message);
+			}
+		}
+	}
+}
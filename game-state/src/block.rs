@@ -5,16 +5,22 @@ pub struct Block {
 	pub brightness_variation: u8,
 	pub colour_variation: u8,
 	pub solid: bool,
+	// Light level given off, up to MAX_LIGHT
+	pub emission: u8,
+	// How much more than one light level is lost spreading into this block
+	pub opacity: u8,
 }
 
 impl Block {
 	pub const fn new(colour: Colour, brightness_variation: u8, colour_variation: u8, solid: bool) -> Self {
-		let offset = (brightness_variation + colour_variation) / 2;
+		let offset = ((brightness_variation as u16 + colour_variation as u16) / 2) as u8;
 		Self {
-			colour: Colour::rgba(colour.r - offset, colour.g - offset, colour.b - offset, colour.a),
+			colour: Colour::rgba(colour.r.saturating_sub(offset), colour.g.saturating_sub(offset), colour.b.saturating_sub(offset), colour.a),
 			brightness_variation,
 			colour_variation,
 			solid,
+			emission: 0,
+			opacity: if solid { Self::SOLID_OPACITY } else { 0 },
 		}
 	}
 	
@@ -25,4 +31,6 @@ impl Block {
 	pub const fn bg(colour: Colour, brightness_variation: u8, colour_variation: u8) -> Self {
 		Block::new(colour, brightness_variation, colour_variation, false)
 	}
+	
+	pub const SOLID_OPACITY: u8 = 3;
 }
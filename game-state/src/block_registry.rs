@@ -0,0 +1,132 @@
+use super::*;
+
+use std::{
+	collections::HashMap,
+	fmt,
+};
+
+use serde::{Serialize, Deserialize};
+
+// Maps namespaced string IDs (e.g. "mars:sand") to the runtime u16 IDs stored in chunks. Runtime
+// IDs depend on registration order, so anything persisted or sent over the network should carry
+// an IdPalette so it can be remapped.
+pub struct BlockRegistry {
+	blocks: Vec<Block>,
+	names: Vec<String>,
+	ids: HashMap<String, u16>,
+}
+
+impl BlockRegistry {
+	pub fn new() -> Self {
+		Self {
+			blocks: Vec::new(),
+			names: Vec::new(),
+			ids: HashMap::new(),
+		}
+	}
+	
+	pub fn register(&mut self, name: &str, block: Block) -> Result<u16, RegistryError> {
+		if !is_valid_name(name) {
+			return Err(RegistryError::InvalidName(String::from(name)));
+		}
+		if self.ids.contains_key(name) {
+			return Err(RegistryError::Duplicate(String::from(name)));
+		}
+		if self.blocks.len() > u16::MAX as usize {
+			return Err(RegistryError::Full);
+		}
+		
+		let id = self.blocks.len() as u16;
+		self.blocks.push(block);
+		self.names.push(String::from(name));
+		self.ids.insert(String::from(name), id);
+		Ok(id)
+	}
+	
+	pub fn id(&self, name: &str) -> Option<u16> {
+		self.ids.get(name).copied()
+	}
+	
+	pub fn name(&self, id: u16) -> &str {
+		&self.names[id as usize]
+	}
+	
+	pub fn block(&self, id: u16) -> &Block {
+		&self.blocks[id as usize]
+	}
+	
+	pub fn len(&self) -> usize {
+		self.blocks.len()
+	}
+	
+	pub fn palette(&self) -> IdPalette {
+		IdPalette::new(self.names.clone())
+	}
+}
+
+// A table of string IDs indexed by the u16 IDs used when some data was written
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct IdPalette {
+	names: Vec<String>,
+}
+
+impl IdPalette {
+	pub fn new(names: Vec<String>) -> Self {
+		Self {
+			names,
+		}
+	}
+	
+	pub fn names(&self) -> &[String] {
+		&self.names
+	}
+	
+	pub fn name(&self, id: u16) -> Option<&str> {
+		self.names.get(id as usize).map(String::as_str)
+	}
+	
+	pub fn len(&self) -> usize {
+		self.names.len()
+	}
+	
+	// Returns a table converting IDs from this palette to IDs in the given registry. Names the
+	// registry doesn't have become the fallback if there is one.
+	pub fn remap(&self, registry: &BlockRegistry, fallback: Option<u16>) -> Result<Vec<u16>, RegistryError> {
+		self.names.iter()
+			.map(|name| registry.id(name).or(fallback).ok_or_else(|| RegistryError::UnknownBlock(name.clone())))
+			.collect()
+	}
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+	InvalidName(String),
+	Duplicate(String),
+	Full,
+	UnknownBlock(String),
+}
+
+impl fmt::Display for RegistryError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			RegistryError::InvalidName(name) => write!(f, "\"{}\" is not a valid block ID, expected \"namespace:name\"", name),
+			RegistryError::Duplicate(name) => write!(f, "Block \"{}\" is registered more than once", name),
+			RegistryError::Full => write!(f, "Too many blocks, at most {} are allowed", u16::MAX as usize + 1),
+			RegistryError::UnknownBlock(name) => write!(f, "Unknown block \"{}\"", name),
+		}
+	}
+}
+
+impl std::error::Error for RegistryError { }
+
+fn is_valid_name(name: &str) -> bool {
+	fn is_valid_part(part: &str) -> bool {
+		!part.is_empty() && part.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+	}
+	
+	let mut parts = name.split(':');
+	match (parts.next(), parts.next(), parts.next()) {
+		(Some(namespace), Some(path), None) => is_valid_part(namespace) && is_valid_part(path),
+		_ => false,
+	}
+}
@@ -0,0 +1,145 @@
+use super::*;
+
+use std::{
+	convert::TryInto,
+	fmt,
+};
+
+use higher_order_functions::Init;
+use sized_matrix::Vector;
+
+// Chunks are stored as a version byte, a palette of the string IDs used in the chunk, and then
+// run-length encoded palette indices in row order. All integers are little endian u16s.
+
+const VERSION: u8 = 1;
+
+pub fn encode_chunk(chunk: &Chunk, blocks: &BlockRegistry) -> Vec<u8> {
+	let mut palette: Vec<u16> = Vec::new();
+	let mut runs: Vec<(u16, u16)> = Vec::new();
+	
+	for y in 0..Chunk::I_SIZE {
+		for x in 0..Chunk::I_SIZE {
+			let id = chunk.get(Vector::vector([x, y]));
+			let index = match palette.iter().position(|&p| p == id) {
+				Some(index) => index as u16,
+				None => {
+					palette.push(id);
+					(palette.len() - 1) as u16
+				},
+			};
+			match runs.last_mut() {
+				Some((last, length)) if *last == index => *length += 1,
+				_ => runs.push((index, 1)),
+			}
+		}
+	}
+	
+	let mut data = vec![VERSION];
+	write_u16(&mut data, palette.len() as u16);
+	for &id in &palette {
+		let name = blocks.name(id).as_bytes();
+		write_u16(&mut data, name.len() as u16);
+		data.extend_from_slice(name);
+	}
+	for (index, length) in runs {
+		write_u16(&mut data, index);
+		write_u16(&mut data, length);
+	}
+	data
+}
+
+// Blocks missing from the registry are an error, unless there is a fallback to replace them with
+pub fn decode_chunk(data: &[u8], blocks: &BlockRegistry, fallback: Option<u16>) -> Result<Chunk, ChunkFormatError> {
+	let mut reader = Reader { data };
+	
+	let version = reader.read_u8()?;
+	if version != VERSION {
+		return Err(ChunkFormatError::UnsupportedVersion(version));
+	}
+	
+	let palette_len = reader.read_u16()?;
+	let mut names = Vec::new();
+	for _ in 0..palette_len {
+		let len = reader.read_u16()?;
+		let name = std::str::from_utf8(reader.read_bytes(len as usize)?)
+			.map_err(|_| ChunkFormatError::InvalidName)?;
+		names.push(String::from(name));
+	}
+	let palette = IdPalette::new(names).remap(blocks, fallback)
+		.map_err(|err| match err {
+			RegistryError::UnknownBlock(name) => ChunkFormatError::UnknownBlock(name),
+			_ => ChunkFormatError::InvalidName,
+		})?;
+	
+	let mut cells = Vec::with_capacity(Chunk::SIZE * Chunk::SIZE);
+	while cells.len() < Chunk::SIZE * Chunk::SIZE {
+		let index = reader.read_u16()?;
+		let length = reader.read_u16()?;
+		let id = *palette.get(index as usize).ok_or(ChunkFormatError::InvalidPaletteIndex(index))?;
+		if length == 0 || cells.len() + length as usize > Chunk::SIZE * Chunk::SIZE {
+			return Err(ChunkFormatError::InvalidRun);
+		}
+		cells.extend(std::iter::repeat(id).take(length as usize));
+	}
+	if !reader.data.is_empty() {
+		return Err(ChunkFormatError::TrailingData);
+	}
+	
+	let mut chunk = Chunk::init(|pos: Vector<usize, 2>| cells[pos[1] * Chunk::SIZE + pos[0]]);
+	chunk.mark_modified();
+	Ok(chunk)
+}
+
+#[derive(Debug)]
+pub enum ChunkFormatError {
+	UnexpectedEnd,
+	UnsupportedVersion(u8),
+	InvalidName,
+	UnknownBlock(String),
+	InvalidPaletteIndex(u16),
+	InvalidRun,
+	TrailingData,
+}
+
+impl fmt::Display for ChunkFormatError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ChunkFormatError::UnexpectedEnd => write!(f, "Chunk data ended unexpectedly"),
+			ChunkFormatError::UnsupportedVersion(version) => write!(f, "Unsupported chunk format version {}", version),
+			ChunkFormatError::InvalidName => write!(f, "Chunk palette contains an invalid block ID"),
+			ChunkFormatError::UnknownBlock(name) => write!(f, "Chunk palette contains unknown block \"{}\"", name),
+			ChunkFormatError::InvalidPaletteIndex(index) => write!(f, "Chunk refers to missing palette entry {}", index),
+			ChunkFormatError::InvalidRun => write!(f, "Chunk contains an invalid run"),
+			ChunkFormatError::TrailingData => write!(f, "Chunk data continues past the end of the chunk"),
+		}
+	}
+}
+
+impl std::error::Error for ChunkFormatError { }
+
+fn write_u16(data: &mut Vec<u8>, value: u16) {
+	data.extend_from_slice(&value.to_le_bytes());
+}
+
+struct Reader<'a> {
+	data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+	fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], ChunkFormatError> {
+		if self.data.len() < len {
+			return Err(ChunkFormatError::UnexpectedEnd);
+		}
+		let (bytes, rest) = self.data.split_at(len);
+		self.data = rest;
+		Ok(bytes)
+	}
+	
+	fn read_u8(&mut self) -> Result<u8, ChunkFormatError> {
+		Ok(self.read_bytes(1)?[0])
+	}
+	
+	fn read_u16(&mut self) -> Result<u16, ChunkFormatError> {
+		Ok(u16::from_le_bytes(self.read_bytes(2)?.try_into().unwrap()))
+	}
+}
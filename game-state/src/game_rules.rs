@@ -1,9 +1,16 @@
 use super::*;
 
-use std::convert::TryFrom;
+use std::{
+	convert::TryFrom,
+	fs,
+	path::Path,
+	str::FromStr,
+};
 
 use lib::{Colour, weighted_random};
 
+use lazy_static::lazy_static;
+
 use higher_order_functions::{Init, Map};
 use sized_matrix::Vector;
 use noise_fn::{
@@ -24,8 +31,12 @@ type GameNoise = AddNoise<ScaleNoise<Octaves<Simplex, 10>, f64, f64>, IgnoreSeed
 type DataNoise = ToFloat<HashNoise>;
 
 pub struct GameRules {
-	blocks: Vec<Block>,
+	blocks: BlockRegistry,
+	dug_block: u16,
 	layers: Vec<Layer>,
+	physics: PhysicsRules,
+	lighting: LightingRules,
+	reach: f64,
 	depth: Config<GameNoise>,
 	data: Config<DataNoise>,
 }
@@ -62,57 +73,131 @@ fn register<T>(list: &mut Vec<T>, value: T) -> u16 {
 
 impl GameRules {
 	pub fn load() -> Self {
-		let mut blocks = Vec::new();
-		let b = &mut blocks;
+		Self::from_str(Self::DEFAULT_RULES).expect("Bundled rules should be valid")
+	}
+	
+	// The bundled rules, loaded once and shared, since worlds borrow their rules for as long as they exist
+	pub fn bundled() -> &'static Self {
+		lazy_static! {
+			static ref BUNDLED: GameRules = GameRules::load();
+		}
+		&BUNDLED
+	}
+	
+	pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Self, RulesError> {
+		let text = fs::read_to_string(path).map_err(|err| RulesError::Io(err.to_string()))?;
+		Self::from_str(&text)
+	}
+	
+	pub fn from_file(file: RulesFile) -> Result<Self, RulesError> {
+		validate_values(&file)?;
+		
+		let mut blocks = BlockRegistry::new();
+		
+		for def in file.blocks {
+			let colour = Colour::try_from(def.colour.as_str())
+				.map_err(|_| RulesError::InvalidColour { block: def.name.clone(), colour: def.colour.clone() })?;
+			if def.opacity.map_or(false, |opacity| opacity > MAX_LIGHT) {
+				return Err(RulesError::InvalidOpacity(def.name));
+			}
+			let block = Block::new(colour, def.brightness_variation, def.colour_variation, def.solid);
+			let block = Block {
+				emission: def.emission.min(MAX_LIGHT),
+				opacity: def.opacity.unwrap_or(block.opacity),
+				..block
+			};
+			blocks.register(&def.name, block)
+				.map_err(|err| match err {
+					RegistryError::InvalidName(name) => RulesError::InvalidBlockName(name),
+					RegistryError::Duplicate(name) => RulesError::DuplicateBlock(name),
+					_ => RulesError::TooManyBlocks,
+				})?;
+		}
 		
-		let space = register(b, Block::bg(Colour::try_from("#050505").unwrap(), 10, 0));
-		let star = register(b, Block::bg(Colour::try_from("#999").unwrap(), 100, 100));
-		let sand = register(b, Block::fg(Colour::try_from("#C82").unwrap(), 20, 5));
-		let stone = register(b, Block::fg(Colour::try_from("#742").unwrap(), 40, 10));
-		let rock = register(b, Block::fg(Colour::try_from("#432").unwrap(), 20, 5));
-		let cold_magma = register(b, Block::fg(Colour::try_from("#c51").unwrap(), 25, 0));
-		let warm_magma = register(b, Block::fg(Colour::try_from("#e81").unwrap(), 25, 0));
-		let hot_magma = register(b, Block::fg(Colour::try_from("#eb2").unwrap(), 25, 0));
-		let alien = register(b, Block::fg(Colour::try_from("#834").unwrap(), 40, 40));
+		let dug_block = file.dug_block;
+		let dug_block = blocks.id(&dug_block)
+			.ok_or_else(|| RulesError::UnknownBlock { context: String::from("dug_block"), block: dug_block.clone() })?;
 		
 		let mut layers = Vec::new();
-		let l = &mut layers;
 		
-		register(l, Layer::new(f64::NEG_INFINITY, 0., vec![(space, 0.999), (star, 0.001)]));
-		register(l, Layer::new(0., 200., vec![(sand, 0.99), (stone, 0.01)]));
-		register(l, Layer::new(50., 1100., vec![(rock, 1.0)]));
-		register(l, Layer::new(500., 1700., vec![(cold_magma, 1.0)]));
-		register(l, Layer::new(900., 2100., vec![(warm_magma, 1.0)]));
-		register(l, Layer::new(1300., 2500., vec![(hot_magma, 1.0)]));
-		register(l, Layer::new(1900., f64::INFINITY, vec![(alien, 1.0)]));
+		for (index, def) in file.layers.into_iter().enumerate() {
+			let start = def.start.unwrap_or(f64::NEG_INFINITY);
+			let end = def.end.unwrap_or(f64::INFINITY);
+			if !(start <= end) {
+				return Err(RulesError::InvalidLayerRange(index));
+			}
+			if def.blocks.is_empty() {
+				return Err(RulesError::EmptyLayer(index));
+			}
+			let mut palette = Vec::new();
+			for (name, weight) in def.blocks {
+				if !(weight > 0. && weight.is_finite()) {
+					return Err(RulesError::InvalidWeight { layer: index, block: name });
+				}
+				match blocks.id(&name) {
+					Some(id) => palette.push((id, weight)),
+					None => return Err(RulesError::UnknownBlock { context: format!("Layer {}", index), block: name }),
+				}
+			}
+			register(&mut layers, Layer::new(start, end, palette));
+		}
 		
-		Self {
+		let depth = file.depth;
+		
+		Ok(Self {
 			blocks,
+			dug_block,
 			layers,
+			physics: file.physics,
+			lighting: file.lighting,
+			reach: file.reach,
 			depth: AddNoise::new(
 				ScaleNoise::new(
 					Octaves::new(
 						Simplex::new(),
-						2.0, 0.5,
+						depth.lacunarity, depth.persistence,
 					),
-					1.0 / 4096.0, 192.0,
+					depth.frequency, depth.amplitude,
 				),
 				IgnoreSeed::new(
 					Gradient::new(
-						Vector::vector([0.0, 1.0]),
+						Vector::vector(depth.gradient),
 					)
 				),
 			),
 			data: ToFloat::new(
 				HashNoise::new(),
 			),
-		}
+		})
 	}
 	
 	pub fn block(&self, id: u16) -> &Block {
-		&self.blocks[id as usize]
+		self.blocks.block(id)
 	}
 	
+	pub fn blocks(&self) -> &BlockRegistry {
+		&self.blocks
+	}
+	
+	pub fn dug_block(&self) -> u16 {
+		self.dug_block
+	}
+	
+	pub fn physics(&self) -> &PhysicsRules {
+		&self.physics
+	}
+	
+	pub fn lighting(&self) -> &LightingRules {
+		&self.lighting
+	}
+	
+	// How far from the centre of the player blocks can be dug or placed
+	pub fn reach(&self) -> f64 {
+		self.reach
+	}
+	
+	const DEFAULT_RULES: &'static str = include_str!("../rules/default.ron");
+	
 	pub fn generate_chunk(&self, world: &WorldGenParams, chunk: Vector<i32, 2>) -> Chunk {
 		let chunk = Chunk::init(|local| -> u16 {
 			let pos = chunk * Chunk::I_SIZE + local.map(|x| x as i32);
@@ -151,6 +236,48 @@ impl GameRules {
 	}
 }
 
+impl FromStr for GameRules {
+	type Err = RulesError;
+	
+	fn from_str(text: &str) -> Result<Self, RulesError> {
+		let file: RulesFile = ron::from_str(text).map_err(|err| RulesError::Parse(err.to_string()))?;
+		Self::from_file(file)
+	}
+}
+
+// Rejects values that would divide by zero or make the game unplayable, naming the field
+fn validate_values(file: &RulesFile) -> Result<(), RulesError> {
+	let physics = &file.physics;
+	let lighting = &file.lighting;
+	let positive = |x: f64| x > 0.;
+	let non_negative = |x: f64| x >= 0.;
+	let fields: [(&str, f64, bool); 17] = [
+		("reach", file.reach, positive(file.reach)),
+		("physics.surface_gravity", physics.surface_gravity, non_negative(physics.surface_gravity)),
+		("physics.deep_gravity", physics.deep_gravity, non_negative(physics.deep_gravity)),
+		("physics.deep_gravity_depth", physics.deep_gravity_depth, positive(physics.deep_gravity_depth)),
+		("physics.max_fall_speed", physics.max_fall_speed, positive(physics.max_fall_speed)),
+		("physics.walk_speed", physics.walk_speed, non_negative(physics.walk_speed)),
+		("physics.ground_acceleration", physics.ground_acceleration, non_negative(physics.ground_acceleration)),
+		("physics.air_acceleration", physics.air_acceleration, non_negative(physics.air_acceleration)),
+		("physics.jump_speed", physics.jump_speed, non_negative(physics.jump_speed)),
+		("physics.jetpack_thrust", physics.jetpack_thrust, non_negative(physics.jetpack_thrust)),
+		("physics.jetpack_fuel", physics.jetpack_fuel, non_negative(physics.jetpack_fuel)),
+		("physics.jetpack_refuel_rate", physics.jetpack_refuel_rate, non_negative(physics.jetpack_refuel_rate)),
+		("lighting.dark_start_depth", lighting.dark_start_depth, true),
+		("lighting.dark_end_depth", lighting.dark_end_depth, lighting.dark_end_depth > lighting.dark_start_depth),
+		("lighting.min_ambient", lighting.min_ambient, non_negative(lighting.min_ambient) && lighting.min_ambient <= 1.),
+		("lighting.lamp_radius", lighting.lamp_radius, positive(lighting.lamp_radius)),
+		("lighting.lamp_brightness", lighting.lamp_brightness, non_negative(lighting.lamp_brightness)),
+	];
+	for &(field, value, valid) in fields.iter() {
+		if !(valid && value.is_finite()) {
+			return Err(RulesError::InvalidValue(String::from(field)));
+		}
+	}
+	Ok(())
+}
+
 pub struct WorldGenParams {
 	depth: GameNoise,
 	data: DataNoise,
@@ -163,4 +290,8 @@ impl WorldGenParams {
 			data: rules.data.seed(seed),
 		}
 	}
+	
+	pub fn depth(&self, pos: Vector<f64, 2>) -> f64 {
+		self.depth.noise(pos)
+	}
 }
@@ -1,11 +1,466 @@
 mod block;
 pub use block::*;
 
+mod block_registry;
+pub use block_registry::*;
+
+mod chunk_format;
+pub use chunk_format::*;
+
 mod game_rules;
 pub use game_rules::*;
 
+mod light;
+pub use light::*;
+
+mod lighting;
+pub use lighting::*;
+
+mod physics;
+pub use physics::*;
+
 mod player;
 pub use player::*;
 
+mod protocol;
+pub use protocol::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod region;
+#[cfg(not(target_arch = "wasm32"))]
+pub use region::*;
+
+mod rules_file;
+pub use rules_file::*;
+
+mod storage;
+pub use storage::*;
+
 mod world;
 pub use world::*;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	use std::str::FromStr;
+	
+	use lib::Colour;
+	
+	use higher_order_functions::Init;
+	use sized_matrix::Vector;
+	
+	const RULES: &str = include_str!("../rules/default.ron");
+	
+	// The bundled rules with one piece of text replaced
+	fn edited_rules(from: &str, to: &str) -> Result<GameRules, RulesError> {
+		assert!(RULES.contains(from), "Bundled rules don't contain {:?}", from);
+		GameRules::from_str(&RULES.replacen(from, to, 1))
+	}
+	
+	#[test]
+	fn bundled_rules_parse() {
+		let rules = GameRules::from_str(RULES).unwrap();
+		assert_eq!(rules.blocks().name(rules.dug_block()), "mars:tunnel");
+		assert_eq!(rules.block(rules.blocks().id("mars:hot_magma").unwrap()).emission, 13);
+		assert_eq!(rules.reach(), 5.);
+		
+		// Rules files from before reach was configurable get the bundled value
+		assert_eq!(edited_rules("reach: 5.0,", "").unwrap().reach(), 5.);
+	}
+	
+	#[test]
+	fn invalid_rules_are_rejected() {
+		assert!(matches!(edited_rules("blocks: [", "blocks: ("), Err(RulesError::Parse(_))));
+		assert!(matches!(edited_rules("\"#C82\"", "\"#C8Z\""), Err(RulesError::InvalidColour { block, .. }) if block == "mars:sand"));
+		assert!(matches!(edited_rules("name: \"mars:star\"", "name: \"mars:space\""), Err(RulesError::DuplicateBlock(block)) if block == "mars:space"));
+		assert!(matches!(edited_rules("name: \"mars:star\"", "name: \"star\""), Err(RulesError::InvalidBlockName(block)) if block == "star"));
+		assert!(matches!(edited_rules("solid: true, emission: 7", "solid: true, emission: 7, opacity: Some(16)"), Err(RulesError::InvalidOpacity(block)) if block == "mars:cold_magma"));
+		assert!(matches!(edited_rules("dug_block: \"mars:tunnel\"", "dug_block: \"mars:hole\""), Err(RulesError::UnknownBlock { context, block }) if context == "dug_block" && block == "mars:hole"));
+		assert!(matches!(edited_rules("(\"mars:alien\", 1.0)", "(\"mars:ghost\", 1.0)"), Err(RulesError::UnknownBlock { block, .. }) if block == "mars:ghost"));
+		assert!(matches!(edited_rules("[(\"mars:rock\", 1.0)]", "[]"), Err(RulesError::EmptyLayer(2))));
+		assert!(matches!(edited_rules("start: Some(50.0)", "start: Some(1200.0)"), Err(RulesError::InvalidLayerRange(2))));
+		assert!(matches!(edited_rules("(\"mars:rock\", 1.0)", "(\"mars:rock\", 0.0)"), Err(RulesError::InvalidWeight { layer: 2, .. })));
+	}
+	
+	#[test]
+	fn block_names_are_namespaced() {
+		let mut registry = BlockRegistry::new();
+		for &name in ["mars:sand", "mars:sand_2", "my_mod:rock9"].iter() {
+			assert!(registry.register(name, Block::fg(Colour::grey(0), 0, 0)).is_ok(), "{} was rejected", name);
+		}
+		for &name in ["sand", "Mars:sand", "mars:sand:x", ":sand", "mars:", "mars:red sand", ""].iter() {
+			assert!(matches!(registry.register(name, Block::fg(Colour::grey(0), 0, 0)), Err(RegistryError::InvalidName(_))), "{} was accepted", name);
+		}
+		assert!(matches!(registry.register("mars:sand", Block::bg(Colour::grey(0), 0, 0)), Err(RegistryError::Duplicate(name)) if name == "mars:sand"));
+		assert_eq!(registry.len(), 3);
+	}
+	
+	#[test]
+	fn palettes_remap_to_current_ids() {
+		let mut registry = BlockRegistry::new();
+		let sand = registry.register("mars:sand", Block::fg(Colour::grey(0), 0, 0)).unwrap();
+		let rock = registry.register("mars:rock", Block::fg(Colour::grey(0), 0, 0)).unwrap();
+		
+		let palette = IdPalette::new(vec![String::from("mars:rock"), String::from("mars:sand")]);
+		assert_eq!(palette.remap(&registry, None).unwrap(), vec![rock, sand]);
+		
+		let palette = IdPalette::new(vec![String::from("mars:rock"), String::from("mars:ice")]);
+		assert!(matches!(palette.remap(&registry, None), Err(RegistryError::UnknownBlock(name)) if name == "mars:ice"));
+		assert_eq!(palette.remap(&registry, Some(sand)).unwrap(), vec![rock, sand]);
+	}
+	
+	#[test]
+	fn out_of_range_values_are_rejected() {
+		for &(from, to, field) in [
+			("reach: 5.0", "reach: 0.0", "reach"),
+			("deep_gravity_depth: 2000.0", "deep_gravity_depth: 0.0", "physics.deep_gravity_depth"),
+			("jump_speed: 12.0", "jump_speed: -1.0", "physics.jump_speed"),
+			("walk_speed: 8.0", "walk_speed: inf", "physics.walk_speed"),
+			("dark_end_depth: 200.0", "dark_end_depth: 20.0", "lighting.dark_end_depth"),
+			("min_ambient: 0.05", "min_ambient: 2.0", "lighting.min_ambient"),
+			("lamp_radius: 10.0", "lamp_radius: 0.0", "lighting.lamp_radius"),
+		].iter() {
+			match edited_rules(from, to) {
+				Err(RulesError::InvalidValue(name)) => assert_eq!(name, field),
+				Err(err) => panic!("{} gave the wrong error: {}", to, err),
+				Ok(_) => panic!("{} was accepted", to),
+			}
+		}
+	}
+	
+	fn registry(names: &[&str]) -> BlockRegistry {
+		let mut registry = BlockRegistry::new();
+		for name in names {
+			registry.register(name, Block::fg(Colour::grey(0), 0, 0)).unwrap();
+		}
+		registry
+	}
+	
+	fn striped_chunk() -> Chunk {
+		Chunk::init(|pos: Vector<usize, 2>| ((pos[0] / 3 + pos[1]) % 3) as u16)
+	}
+	
+	#[test]
+	fn chunks_round_trip() {
+		let blocks = registry(&["mars:sand", "mars:rock", "mars:ice"]);
+		let chunk = striped_chunk();
+		let decoded = decode_chunk(&encode_chunk(&chunk, &blocks), &blocks, None).unwrap();
+		for y in 0..Chunk::I_SIZE {
+			for x in 0..Chunk::I_SIZE {
+				assert_eq!(decoded.get(Vector::vector([x, y])), chunk.get(Vector::vector([x, y])));
+			}
+		}
+		assert!(decoded.is_modified());
+		
+		// Blocks are stored by name, so registering them in another order keeps them the same
+		let reordered = registry(&["mars:ice", "mars:sand", "mars:rock"]);
+		let decoded = decode_chunk(&encode_chunk(&chunk, &blocks), &reordered, None).unwrap();
+		assert_eq!(reordered.name(decoded.get(Vector::vector([3, 0]))), "mars:rock");
+	}
+	
+	#[test]
+	fn invalid_chunks_are_rejected() {
+		let blocks = registry(&["mars:sand", "mars:rock", "mars:ice"]);
+		let data = encode_chunk(&striped_chunk(), &blocks);
+		
+		assert!(matches!(decode_chunk(&[], &blocks, None), Err(ChunkFormatError::UnexpectedEnd)));
+		assert!(matches!(decode_chunk(&data[..data.len() - 1], &blocks, None), Err(ChunkFormatError::UnexpectedEnd)));
+		assert!(matches!(decode_chunk(&[&data[..], &[0]].concat(), &blocks, None), Err(ChunkFormatError::TrailingData)));
+		assert!(matches!(decode_chunk(&[&[2], &data[1..]].concat(), &blocks, None), Err(ChunkFormatError::UnsupportedVersion(2))));
+		
+		// One palette entry, then a run
+		let chunk_with_run = |index: u16, length: u16| {
+			let mut data = vec![1, 1, 0, 9, 0];
+			data.extend_from_slice(b"mars:sand");
+			data.extend_from_slice(&index.to_le_bytes());
+			data.extend_from_slice(&length.to_le_bytes());
+			data
+		};
+		assert!(decode_chunk(&chunk_with_run(0, 4096), &blocks, None).is_ok());
+		assert!(matches!(decode_chunk(&chunk_with_run(0, 0), &blocks, None), Err(ChunkFormatError::InvalidRun)));
+		assert!(matches!(decode_chunk(&chunk_with_run(0, 4097), &blocks, None), Err(ChunkFormatError::InvalidRun)));
+		assert!(matches!(decode_chunk(&chunk_with_run(1, 4096), &blocks, None), Err(ChunkFormatError::InvalidPaletteIndex(1))));
+		
+		let fewer_blocks = registry(&["mars:sand", "mars:rock"]);
+		assert!(matches!(decode_chunk(&data, &fewer_blocks, None), Err(ChunkFormatError::UnknownBlock(name)) if name == "mars:ice"));
+		let decoded = decode_chunk(&data, &fewer_blocks, Some(0)).unwrap();
+		assert_eq!(decoded.get(Vector::vector([0, 2])), 0);
+		assert_eq!(decoded.get(Vector::vector([0, 1])), 1);
+	}
+	
+	#[test]
+	fn unknown_blocks_are_replaced_when_loading() {
+		let rules = GameRules::bundled();
+		let old_blocks = registry(&["mars:sand", "mars:ice"]);
+		let mut storage = MemoryStorage::new();
+		storage.store(Vector::vector([0, 10]), &encode_chunk(&Chunk::init(|pos: Vector<usize, 2>| if pos[0] == 0 { 1 } else { 0 }), &old_blocks)).unwrap();
+		
+		let mut world = World::with_storage(rules, 123, Box::new(storage));
+		assert_eq!(world.get_id(Vector::vector([0, 640])), rules.dug_block());
+		assert_eq!(world.get_id(Vector::vector([1, 640])), rules.blocks().id("mars:sand").unwrap());
+		assert!(world.is_modified(Vector::vector([0, 10])));
+	}
+	
+	#[test]
+	fn regions_round_trip() {
+		let mut region = Region::new();
+		region.insert(Vector::vector([0, 0]), vec![1, 2, 3]);
+		region.insert(Vector::vector([31, 5]), vec![]);
+		let data = encode_region(&region);
+		assert!(decode_region(&data).unwrap() == region);
+		assert!(decode_region(&data[..data.len() - 1]).is_err());
+	}
+	
+	#[test]
+	fn regions_are_written_on_flush() {
+		let directory = std::env::temp_dir().join(format!("game-state-regions-{}", std::process::id()));
+		let mut storage = RegionStorage::new(&directory).unwrap();
+		storage.store(Vector::vector([-1, 0]), &[1, 2]).unwrap();
+		storage.store(Vector::vector([-2, 3]), &[3]).unwrap();
+		assert!(!directory.join("r.-1.0.region").exists());
+		storage.flush().unwrap();
+		
+		let mut reopened = RegionStorage::new(&directory).unwrap();
+		assert_eq!(reopened.load(Vector::vector([-1, 0])).unwrap(), Some(vec![1, 2]));
+		assert_eq!(reopened.load(Vector::vector([-2, 3])).unwrap(), Some(vec![3]));
+		assert_eq!(reopened.load(Vector::vector([0, 0])).unwrap(), None);
+		std::fs::remove_dir_all(directory).unwrap();
+	}
+	
+	struct FailingStorage;
+	
+	impl ChunkStorage for FailingStorage {
+		fn load(&mut self, _: Vector<i32, 2>) -> std::io::Result<Option<Vec<u8>>> {
+			Ok(None)
+		}
+		
+		fn store(&mut self, _: Vector<i32, 2>, _: &[u8]) -> std::io::Result<()> {
+			Err(std::io::Error::new(std::io::ErrorKind::Other, "Storage full"))
+		}
+	}
+	
+	#[test]
+	fn modified_chunks_kept_when_they_cannot_be_saved() {
+		let rules = GameRules::bundled();
+		for mut world in vec![World::new(rules, 123), World::with_storage(rules, 123, Box::new(FailingStorage))] {
+			world.set_memory_budget(0);
+			// More modified chunks than the minimum kept, none of which can be evicted
+			for x in 0..100 {
+				world.set(Vector::vector([x * 64, 0]), rules.dug_block());
+			}
+			assert!((0..100).all(|x| world.is_modified(Vector::vector([x, 0]))));
+		}
+	}
+	
+	// A rectangle of tiles, of which only some are loaded
+	struct TestGrid {
+		size: Vector<i32, 2>,
+		loaded: Vec<bool>,
+		light: Vec<u8>,
+		emission: Vec<u8>,
+		opacity: Vec<u8>,
+	}
+	
+	impl TestGrid {
+		fn new(size: Vector<i32, 2>) -> Self {
+			let count = (size[0] * size[1]) as usize;
+			Self {
+				size,
+				loaded: vec![true; count],
+				light: vec![0; count],
+				emission: vec![0; count],
+				opacity: vec![0; count],
+			}
+		}
+		
+		fn index(&self, pos: Vector<i32, 2>) -> Option<usize> {
+			if pos[0] >= 0 && pos[1] >= 0 && pos[0] < self.size[0] && pos[1] < self.size[1] {
+				Some((pos[1] * self.size[0] + pos[0]) as usize)
+			} else {
+				None
+			}
+		}
+		
+		// Loading starts unlit, as a generated chunk does
+		fn set_loaded(&mut self, min: Vector<i32, 2>, size: i32, loaded: bool) {
+			for y in 0..size {
+				for x in 0..size {
+					let index = self.index(min + Vector::vector([x, y])).unwrap();
+					self.loaded[index] = loaded;
+					self.light[index] = 0;
+				}
+			}
+		}
+		
+		// The light along the edges of a square, as the world passes when unloading a chunk
+		fn edges(&self, min: Vector<i32, 2>, size: i32) -> Vec<(Vector<i32, 2>, u8)> {
+			let mut edges = Vec::new();
+			for i in 0..size {
+				for &local in [[i, 0], [i, size - 1], [0, i], [size - 1, i]].iter() {
+					let pos = min + Vector::vector(local);
+					edges.push((pos, self.light[self.index(pos).unwrap()]));
+				}
+			}
+			edges
+		}
+		
+		fn light_all(&mut self) {
+			for level in self.light.iter_mut() {
+				*level = 0;
+			}
+			let size = self.size[0].max(self.size[1]);
+			light_area(self, Vector::vector([0, 0]), size);
+		}
+	}
+	
+	impl LightGrid for TestGrid {
+		fn light(&self, pos: Vector<i32, 2>) -> Option<u8> {
+			self.index(pos).filter(|&index| self.loaded[index]).map(|index| self.light[index])
+		}
+		
+		fn set_light(&mut self, pos: Vector<i32, 2>, level: u8) {
+			if let Some(index) = self.index(pos).filter(|&index| self.loaded[index]) {
+				self.light[index] = level;
+			}
+		}
+		
+		fn emission(&self, pos: Vector<i32, 2>) -> u8 {
+			self.index(pos).map_or(0, |index| self.emission[index])
+		}
+		
+		fn opacity(&self, pos: Vector<i32, 2>) -> u8 {
+			self.index(pos).map_or(0, |index| self.opacity[index])
+		}
+	}
+	
+	// Changes a tile's blocks and checks the light is the same as lighting everything from scratch
+	fn assert_update_matches(grid: &mut TestGrid, pos: Vector<i32, 2>, emission: u8, opacity: u8) {
+		let index = grid.index(pos).unwrap();
+		grid.emission[index] = emission;
+		grid.opacity[index] = opacity;
+		update_light(grid, pos);
+		let updated = grid.light.clone();
+		grid.light_all();
+		assert!(updated == grid.light, "Changing {:?} gave the wrong light", pos);
+	}
+	
+	#[test]
+	fn light_spreads_from_emitters() {
+		let mut grid = TestGrid::new(Vector::vector([16, 16]));
+		let emitter = grid.index(Vector::vector([5, 5])).unwrap();
+		grid.emission[emitter] = 10;
+		grid.light_all();
+		
+		assert_eq!(grid.light(Vector::vector([5, 5])), Some(10));
+		assert_eq!(grid.light(Vector::vector([8, 5])), Some(7));
+		assert_eq!(grid.light(Vector::vector([7, 7])), Some(6));
+		assert_eq!(grid.light(Vector::vector([15, 15])), Some(0));
+	}
+	
+	#[test]
+	fn light_updates_when_blocks_change() {
+		let mut grid = TestGrid::new(Vector::vector([16, 16]));
+		let emitter = Vector::vector([5, 5]);
+		assert_update_matches(&mut grid, emitter, 10, 0);
+		
+		// An opaque block right next to the emitter
+		let wall = Vector::vector([6, 5]);
+		assert_update_matches(&mut grid, wall, 0, 3);
+		assert_eq!(grid.light(wall), Some(6));
+		assert_eq!(grid.light(Vector::vector([7, 5])), Some(6));
+		
+		// Walls that block light entirely, and then more light behind them
+		for y in 0..16 {
+			assert_update_matches(&mut grid, Vector::vector([6, y]), 0, MAX_LIGHT);
+		}
+		assert_eq!(grid.light(Vector::vector([7, 5])), Some(0));
+		assert_update_matches(&mut grid, Vector::vector([9, 5]), 4, 0);
+		assert_update_matches(&mut grid, Vector::vector([6, 5]), 0, 0);
+		assert_update_matches(&mut grid, emitter, 0, 0);
+		assert_update_matches(&mut grid, Vector::vector([9, 5]), 0, 0);
+		assert!(grid.light.iter().all(|&level| level == 0));
+	}
+	
+	#[test]
+	fn light_crosses_area_borders() {
+		let left = Vector::vector([0, 0]);
+		let right = Vector::vector([16, 0]);
+		let mut grid = TestGrid::new(Vector::vector([32, 16]));
+		grid.set_loaded(right, 16, false);
+		let emitter = grid.index(Vector::vector([14, 8])).unwrap();
+		grid.emission[emitter] = 10;
+		light_area(&mut grid, left, 16);
+		assert_eq!(grid.light(Vector::vector([15, 8])), Some(9));
+		assert_eq!(grid.light(Vector::vector([16, 8])), None);
+		
+		// Light spills into an area as it loads, and is left alone when it unloads
+		grid.set_loaded(right, 16, true);
+		light_area(&mut grid, right, 16);
+		assert_eq!(grid.light(Vector::vector([20, 8])), Some(4));
+		let edges = grid.edges(right, 16);
+		grid.set_loaded(right, 16, false);
+		unlight_area(&mut grid, edges);
+		assert_eq!(grid.light(Vector::vector([15, 8])), Some(9));
+		
+		// Light from an area loaded later spills back out, and is cleared when it unloads
+		grid.emission[emitter] = 0;
+		let emitter = grid.index(Vector::vector([17, 8])).unwrap();
+		grid.emission[emitter] = 10;
+		grid.set_loaded(left, 16, true);
+		light_area(&mut grid, left, 16);
+		grid.set_loaded(right, 16, true);
+		light_area(&mut grid, right, 16);
+		assert_eq!(grid.light(Vector::vector([15, 8])), Some(8));
+		let edges = grid.edges(right, 16);
+		grid.set_loaded(right, 16, false);
+		unlight_area(&mut grid, edges);
+		assert!((0..16).all(|x| (0..16).all(|y| grid.light(Vector::vector([x, y])) == Some(0))));
+	}
+	
+	#[test]
+	fn block_light_spreads_across_chunks() {
+		let rules = GameRules::bundled();
+		let mut world = World::new(rules, 123);
+		let magma = rules.blocks().id("mars:hot_magma").unwrap();
+		
+		// A tunnel through the rock crossing the border between two chunks
+		for x in 58..=72 {
+			world.dig(Vector::vector([x, 300]));
+		}
+		world.take_light_changes();
+		world.set(Vector::vector([60, 300]), magma);
+		
+		assert_eq!(world.light(Vector::vector([60, 300])), 13);
+		assert_eq!(world.light(Vector::vector([66, 300])), 7);
+		assert_eq!(world.light(Vector::vector([72, 300])), 1);
+		// Rock takes more light to get through than the tunnel
+		assert_eq!(world.light(Vector::vector([60, 301])), 9);
+		assert!(world.take_light_changes().contains(&Vector::vector([66, 300])));
+		
+		world.dig(Vector::vector([60, 300]));
+		for x in 58..=72 {
+			assert_eq!(world.light(Vector::vector([x, 300])), 0);
+		}
+		assert_eq!(world.light(Vector::vector([60, 301])), 0);
+	}
+	
+	#[test]
+	fn unloading_clears_light_from_the_chunk() {
+		let rules = GameRules::bundled();
+		let mut world = World::with_storage(rules, 123, Box::new(MemoryStorage::new()));
+		let magma = rules.blocks().id("mars:hot_magma").unwrap();
+		for x in 58..=72 {
+			world.dig(Vector::vector([x, 300]));
+		}
+		world.set(Vector::vector([60, 300]), magma);
+		assert_eq!(world.light(Vector::vector([66, 300])), 7);
+		
+		assert!(world.unload(Vector::vector([0, 4])));
+		assert!((64..=72).all(|x| world.light(Vector::vector([x, 300])) == 0));
+		
+		// Reloading lights it again
+		assert_eq!(world.get_id(Vector::vector([60, 300])), magma);
+		assert_eq!(world.light(Vector::vector([66, 300])), 7);
+	}
+}
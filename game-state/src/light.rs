@@ -0,0 +1,107 @@
+use std::collections::VecDeque;
+
+use sized_matrix::Vector;
+
+// Light from blocks, from 0 for none to MAX_LIGHT
+pub const MAX_LIGHT: u8 = 15;
+
+// The light and blocks of the loaded chunks, with None for light outside them so it never spreads into chunks that aren't loaded
+pub trait LightGrid {
+	fn light(&self, pos: Vector<i32, 2>) -> Option<u8>;
+	fn set_light(&mut self, pos: Vector<i32, 2>, level: u8);
+	fn emission(&self, pos: Vector<i32, 2>) -> u8;
+	fn opacity(&self, pos: Vector<i32, 2>) -> u8;
+}
+
+const NEIGHBOURS: [[i32; 2]; 4] = [[1, 0], [-1, 0], [0, 1], [0, -1]];
+
+// Light falls by one plus the opacity of each tile it spreads into
+fn spread<TGrid: LightGrid>(grid: &mut TGrid, mut queue: VecDeque<Vector<i32, 2>>) {
+	while let Some(pos) = queue.pop_front() {
+		let level = match grid.light(pos) {
+			Some(level) if level > 0 => level,
+			_ => continue,
+		};
+		for offset in NEIGHBOURS.iter() {
+			let next = pos + Vector::vector(*offset);
+			if let Some(current) = grid.light(next) {
+				let spread = level.saturating_sub(1).saturating_sub(grid.opacity(next));
+				if spread > current {
+					grid.set_light(next, spread);
+					queue.push_back(next);
+				}
+			}
+		}
+	}
+}
+
+// Lights a newly loaded square of tiles from its own blocks and the light already around it
+pub fn light_area<TGrid: LightGrid>(grid: &mut TGrid, min: Vector<i32, 2>, size: i32) {
+	let mut queue = VecDeque::new();
+	for y in 0..size {
+		for x in 0..size {
+			let pos = min + Vector::vector([x, y]);
+			let emission = grid.emission(pos);
+			if emission > 0 {
+				grid.set_light(pos, emission);
+				queue.push_back(pos);
+			}
+		}
+	}
+	for i in 0..size {
+		for offset in [[i, -1], [i, size], [-1, i], [size, i]].iter() {
+			let pos = min + Vector::vector(*offset);
+			if grid.light(pos).map_or(false, |level| level > 0) {
+				queue.push_back(pos);
+			}
+		}
+	}
+	spread(grid, queue);
+}
+
+// Fixes the light after the block at pos has changed
+// Light that may have come from or through it is cleared first, then the cleared tiles are refilled from whatever still lights them
+pub fn update_light<TGrid: LightGrid>(grid: &mut TGrid, pos: Vector<i32, 2>) {
+	let old = match grid.light(pos) {
+		Some(old) => old,
+		None => return,
+	};
+	grid.set_light(pos, 0);
+	remove_light(grid, vec![(pos, old)], vec![pos]);
+}
+
+// Clears the light a square of tiles spread around it, once it has been removed from the grid, given the light that was along its edges
+pub fn unlight_area<TGrid: LightGrid>(grid: &mut TGrid, edges: Vec<(Vector<i32, 2>, u8)>) {
+	remove_light(grid, edges, Vec::new());
+}
+
+// Clears light that may have come from each tile at its old level, then refills the cleared tiles from whatever still lights them
+fn remove_light<TGrid: LightGrid>(grid: &mut TGrid, sources: Vec<(Vector<i32, 2>, u8)>, mut removed: Vec<Vector<i32, 2>>) {
+	let mut removal: VecDeque<_> = sources.into_iter().collect();
+	let mut refill = VecDeque::new();
+	
+	while let Some((pos, level)) = removal.pop_front() {
+		for offset in NEIGHBOURS.iter() {
+			let next = pos + Vector::vector(*offset);
+			match grid.light(next) {
+				None | Some(0) => (),
+				// Dimmer neighbours may have been lit from here
+				Some(current) if current < level => {
+					grid.set_light(next, 0);
+					removal.push_back((next, current));
+					removed.push(next);
+				},
+				Some(_) => refill.push_back(next),
+			}
+		}
+	}
+	
+	for pos in removed {
+		let emission = grid.emission(pos);
+		if emission > 0 {
+			grid.set_light(pos, emission);
+			refill.push_back(pos);
+		}
+	}
+	spread(grid, refill);
+}
@@ -0,0 +1,45 @@
+use super::*;
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct LightingRules {
+	// Depth where daylight starts to fade, and where it reaches its darkest
+	pub dark_start_depth: f64,
+	pub dark_end_depth: f64,
+	// Light left once daylight has faded, from 0 to 1
+	pub min_ambient: f64,
+	// The lamp the player carries fades out linearly to nothing at its radius
+	pub lamp_radius: f64,
+	pub lamp_brightness: f64,
+}
+
+impl LightingRules {
+	// Daylight from the depth noise, so caves and surface dips darken with the terrain rather than straight lines
+	pub fn ambient(&self, depth: f64) -> f64 {
+		let t = ((depth - self.dark_start_depth) / (self.dark_end_depth - self.dark_start_depth)).max(0.).min(1.);
+		(1. - t) + self.min_ambient * t
+	}
+	
+	pub fn lamp(&self, distance: f64) -> f64 {
+		self.lamp_brightness * (1. - distance / self.lamp_radius).max(0.)
+	}
+	
+	// Light from all sources, from 0 to 1
+	pub fn combine(&self, ambient: f64, lamp: f64, block: u8) -> f64 {
+		(ambient + lamp + block as f64 / MAX_LIGHT as f64).min(1.)
+	}
+}
+
+impl Default for LightingRules {
+	fn default() -> Self {
+		Self {
+			dark_start_depth: 20.,
+			dark_end_depth: 200.,
+			min_ambient: 0.05,
+			lamp_radius: 10.,
+			lamp_brightness: 1.,
+		}
+	}
+}
@@ -0,0 +1,50 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhysicsRules {
+	pub surface_gravity: f64,
+	pub deep_gravity: f64,
+	pub deep_gravity_depth: f64,
+	pub max_fall_speed: f64,
+	pub walk_speed: f64,
+	pub ground_acceleration: f64,
+	pub air_acceleration: f64,
+	pub jump_speed: f64,
+	pub jetpack_thrust: f64,
+	pub jetpack_fuel: f64,
+	pub jetpack_refuel_rate: f64,
+}
+
+impl PhysicsRules {
+	// Gravity changes linearly from the surface value to the deep value, then stays constant
+	pub fn gravity(&self, depth: f64) -> f64 {
+		let t = (depth / self.deep_gravity_depth).max(0.).min(1.);
+		self.surface_gravity + (self.deep_gravity - self.surface_gravity) * t
+	}
+	
+	// More than the player can ever move in a second, assuming a whole tank of fuel burnt straight after a jump
+	pub fn max_speed(&self) -> f64 {
+		let horizontal = self.walk_speed;
+		let vertical = self.max_fall_speed.max(self.jump_speed + self.jetpack_thrust * self.jetpack_fuel);
+		(horizontal * horizontal + vertical * vertical).sqrt()
+	}
+}
+
+impl Default for PhysicsRules {
+	fn default() -> Self {
+		Self {
+			surface_gravity: 30.,
+			deep_gravity: 20.,
+			deep_gravity_depth: 2000.,
+			max_fall_speed: 40.,
+			walk_speed: 8.,
+			ground_acceleration: 60.,
+			air_acceleration: 20.,
+			jump_speed: 12.,
+			jetpack_thrust: 45.,
+			jetpack_fuel: 2.,
+			jetpack_refuel_rate: 0.5,
+		}
+	}
+}
@@ -1,21 +1,178 @@
+use super::*;
+
 use sized_matrix::Vector;
+use num_traits::Zero;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Facing {
+	Left,
+	Right,
+}
 
 pub struct Player {
 	pos: Vector<f64, 2>,
+	prev_pos: Vector<f64, 2>,
+	vel: Vector<f64, 2>,
+	size: Vector<f64, 2>,
+	grounded: bool,
+	fuel: f64,
+	facing: Facing,
 }
 
 impl Player {
 	pub fn new(pos: Vector<f64, 2>) -> Self {
 		Player {
 			pos,
+			prev_pos: pos,
+			vel: Vector::zero(),
+			size: Vector::vector([Self::WIDTH, Self::HEIGHT]),
+			grounded: false,
+			fuel: 0.,
+			facing: Facing::Right,
 		}
 	}
 	
+	pub fn spawn(world: &mut World, x: i32) -> Self {
+		let y = world.surface(x);
+		let mut player = Self::new(Vector::vector([x as f64 + 0.5, y as f64 - Self::HEIGHT / 2.]));
+		player.fuel = world.rules().physics().jetpack_fuel;
+		player
+	}
+	
 	pub fn pos(&self) -> Vector<f64, 2> {
 		self.pos
 	}
 	
-	pub fn tick(&mut self, k: Vector<f64, 2>) {
-		self.pos += k;
+	// Position between the previous and current tick, for rendering between fixed updates
+	pub fn interpolated_pos(&self, alpha: f64) -> Vector<f64, 2> {
+		self.prev_pos + (self.pos - self.prev_pos) * alpha
+	}
+	
+	pub fn vel(&self) -> Vector<f64, 2> {
+		self.vel
+	}
+	
+	pub fn size(&self) -> Vector<f64, 2> {
+		self.size
+	}
+	
+	pub fn is_grounded(&self) -> bool {
+		self.grounded
+	}
+	
+	pub fn fuel(&self) -> f64 {
+		self.fuel
+	}
+	
+	pub fn facing(&self) -> Facing {
+		self.facing
+	}
+	
+	// Turns towards a point, such as a block being dug
+	pub fn face_towards(&mut self, x: f64) {
+		if x < self.pos[0] {
+			self.facing = Facing::Left;
+		} else if x > self.pos[0] {
+			self.facing = Facing::Right;
+		}
+	}
+	
+	// k is the movement input from -1 to 1, with x for walking and a negative y to jump or use the jetpack
+	pub fn tick(&mut self, world: &mut World, k: Vector<f64, 2>, dt: f64) {
+		self.prev_pos = self.pos;
+		
+		let physics = world.rules().physics();
+		let gravity = physics.gravity(world.depth(self.pos));
+		let up = k[1] < 0.;
+		// Keeps facing the same way when stopped
+		if k[0] != 0. {
+			self.face_towards(self.pos[0] + k[0]);
+		}
+		
+		let acceleration = if self.grounded { physics.ground_acceleration } else { physics.air_acceleration };
+		let vel_x = approach(self.vel[0], k[0] * physics.walk_speed, acceleration * dt);
+		let mut vel_y = self.vel[1] + gravity * dt;
+		
+		if self.grounded {
+			self.fuel = (self.fuel + physics.jetpack_refuel_rate * dt).min(physics.jetpack_fuel);
+			if up {
+				vel_y = -physics.jump_speed;
+			}
+		} else if up && self.fuel > 0. {
+			// Analogue input gives partial thrust
+			let throttle = f64::min(-k[1], 1.);
+			vel_y -= physics.jetpack_thrust * throttle * dt;
+			self.fuel = (self.fuel - throttle * dt).max(0.);
+		}
+		
+		self.vel = Vector::vector([vel_x, vel_y.min(physics.max_fall_speed)]);
+		let [_, hit_y] = self.move_by(world, self.vel * dt);
+		self.grounded = hit_y && vel_y > 0.;
 	}
+	
+	// Moves along each axis in turn so that hitting a wall only stops movement into it, letting
+	// the player slide along the surface
+	fn move_by(&mut self, world: &mut World, delta: Vector<f64, 2>) -> [bool; 2] {
+		let x = self.sweep(world, 0, delta[0]);
+		let y = self.sweep(world, 1, delta[1]);
+		[x, y]
+	}
+	
+	fn sweep(&mut self, world: &mut World, axis: usize, delta: f64) -> bool {
+		if delta == 0. {
+			return false;
+		}
+		
+		let other = 1 - axis;
+		let half = self.size / 2.;
+		let other_min = (self.pos[other] - half[other] + Self::EPSILON).floor() as i32;
+		let other_max = (self.pos[other] + half[other] - Self::EPSILON).floor() as i32;
+		
+		let blocked = |world: &mut World, tile: i32| -> bool {
+			(other_min..=other_max).any(|t| world.get(with_axis(Vector::vector([t, t]), axis, tile)).solid)
+		};
+		
+		if delta > 0. {
+			let edge = self.pos[axis] + half[axis];
+			let first = (edge - Self::EPSILON).ceil() as i32;
+			let last = (edge + delta).ceil() as i32 - 1;
+			for tile in first..=last {
+				if blocked(world, tile) {
+					self.pos = with_axis(self.pos, axis, tile as f64 - half[axis]);
+					self.vel = with_axis(self.vel, axis, 0.);
+					return true;
+				}
+			}
+		} else {
+			let edge = self.pos[axis] - half[axis];
+			let first = (edge + Self::EPSILON).floor() as i32 - 1;
+			let last = (edge + delta).floor() as i32;
+			for tile in (last..=first).rev() {
+				if blocked(world, tile) {
+					self.pos = with_axis(self.pos, axis, (tile + 1) as f64 + half[axis]);
+					self.vel = with_axis(self.vel, axis, 0.);
+					return true;
+				}
+			}
+		}
+		
+		self.pos = with_axis(self.pos, axis, self.pos[axis] + delta);
+		false
+	}
+	
+	pub const WIDTH: f64 = 0.8;
+	pub const HEIGHT: f64 = 1.8;
+	
+	const EPSILON: f64 = 1e-6;
+}
+
+fn with_axis<T: Copy>(vector: Vector<T, 2>, axis: usize, value: T) -> Vector<T, 2> {
+	let mut values = [vector[0], vector[1]];
+	values[axis] = value;
+	Vector::vector(values)
+}
+
+fn approach(value: f64, target: f64, step: f64) -> f64 {
+	if value < target { (value + step).min(target) }
+	else { (value - step).max(target) }
 }
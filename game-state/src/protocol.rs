@@ -0,0 +1,32 @@
+use super::*;
+
+use serde::{Serialize, Deserialize};
+
+// Messages between the game server and clients. Block IDs are the server's runtime IDs, which
+// clients map to their own using the palette sent in ServerMessage::Welcome.
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ClientMessage {
+	Hello { name: String },
+	RequestChunk { chunk: [i32; 2] },
+	Position { pos: [f64; 2] },
+	Dig { pos: [i32; 2] },
+	Place { pos: [i32; 2], block: u16 },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ServerMessage {
+	// Players start at the spawn point, and only move from there as fast as the physics allows
+	Welcome { id: u32, seed: u64, palette: IdPalette, spawn: [f64; 2] },
+	// Cells that differ from the generated chunk, as (y * Chunk::SIZE + x, block)
+	// Sent for edited chunks as they come into range of the player, or when requested
+	ChunkDiff { chunk: [i32; 2], changes: Vec<(u16, u16)> },
+	ChunkRejected { chunk: [i32; 2], reason: String },
+	BlockChanged { pos: [i32; 2], block: u16 },
+	EditRejected { pos: [i32; 2], reason: String },
+	// Where the server still has the player, after a position it didn't believe
+	MoveRejected { pos: [f64; 2] },
+	PlayerJoined { id: u32, name: String },
+	PlayerMoved { id: u32, pos: [f64; 2] },
+	PlayerLeft { id: u32 },
+}
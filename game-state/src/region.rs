@@ -0,0 +1,124 @@
+use super::*;
+
+use std::{
+	collections::{HashMap, HashSet},
+	convert::TryInto,
+	fs,
+	io::{
+		self,
+		ErrorKind,
+	},
+	path::PathBuf,
+};
+
+use sized_matrix::Vector;
+
+// Stores chunks on disk grouped into square regions, one file per region. Each file is a u32
+// entry count followed by entries of (i32 local x, i32 local y, u32 length, data), all little
+// endian. Regions are cached in memory once read, and changed regions are rewritten whole when
+// flushed.
+pub struct RegionStorage {
+	directory: PathBuf,
+	regions: HashMap<Vector<i32, 2>, Region>,
+	unsaved: HashSet<Vector<i32, 2>>,
+}
+
+pub(crate) type Region = HashMap<Vector<i32, 2>, Vec<u8>>;
+
+impl RegionStorage {
+	pub fn new<P: Into<PathBuf>>(directory: P) -> io::Result<Self> {
+		let directory = directory.into();
+		fs::create_dir_all(&directory)?;
+		Ok(Self {
+			directory,
+			regions: HashMap::new(),
+			unsaved: HashSet::new(),
+		})
+	}
+	
+	fn split(chunk: Vector<i32, 2>) -> (Vector<i32, 2>, Vector<i32, 2>) {
+		(
+			Vector::vector([chunk[0].div_euclid(Self::REGION_SIZE), chunk[1].div_euclid(Self::REGION_SIZE)]),
+			Vector::vector([chunk[0].rem_euclid(Self::REGION_SIZE), chunk[1].rem_euclid(Self::REGION_SIZE)]),
+		)
+	}
+	
+	fn path(&self, region: Vector<i32, 2>) -> PathBuf {
+		self.directory.join(format!("r.{}.{}.region", region[0], region[1]))
+	}
+	
+	fn region(&mut self, region: Vector<i32, 2>) -> io::Result<&mut Region> {
+		if !self.regions.contains_key(&region) {
+			let contents = match fs::read(self.path(region)) {
+				Ok(data) => decode_region(&data)?,
+				Err(err) if err.kind() == ErrorKind::NotFound => HashMap::new(),
+				Err(err) => return Err(err),
+			};
+			self.regions.insert(region, contents);
+		}
+		Ok(self.regions.get_mut(&region).unwrap())
+	}
+	
+	const REGION_SIZE: i32 = 32;
+}
+
+impl ChunkStorage for RegionStorage {
+	fn load(&mut self, chunk: Vector<i32, 2>) -> io::Result<Option<Vec<u8>>> {
+		let (region, local) = Self::split(chunk);
+		Ok(self.region(region)?.get(&local).cloned())
+	}
+	
+	fn store(&mut self, chunk: Vector<i32, 2>, data: &[u8]) -> io::Result<()> {
+		let (region, local) = Self::split(chunk);
+		self.region(region)?.insert(local, Vec::from(data));
+		self.unsaved.insert(region);
+		Ok(())
+	}
+	
+	fn flush(&mut self) -> io::Result<()> {
+		for region in self.unsaved.iter().copied().collect::<Vec<_>>() {
+			let path = self.path(region);
+			let encoded = encode_region(&self.regions[&region]);
+			
+			// Write to a temporary file first so a crash can't leave a half written region
+			let temp = path.with_extension("region.tmp");
+			fs::write(&temp, encoded)?;
+			fs::rename(temp, path)?;
+			self.unsaved.remove(&region);
+		}
+		Ok(())
+	}
+}
+
+pub(crate) fn encode_region(region: &Region) -> Vec<u8> {
+	let mut data = Vec::new();
+	data.extend_from_slice(&(region.len() as u32).to_le_bytes());
+	for (local, chunk) in region {
+		data.extend_from_slice(&local[0].to_le_bytes());
+		data.extend_from_slice(&local[1].to_le_bytes());
+		data.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+		data.extend_from_slice(chunk);
+	}
+	data
+}
+
+pub(crate) fn decode_region(mut data: &[u8]) -> io::Result<Region> {
+	fn take<'a>(data: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+		if data.len() < len {
+			return Err(io::Error::new(ErrorKind::InvalidData, "Region file ended unexpectedly"));
+		}
+		let (bytes, rest) = data.split_at(len);
+		*data = rest;
+		Ok(bytes)
+	}
+	
+	let count = u32::from_le_bytes(take(&mut data, 4)?.try_into().unwrap());
+	let mut region = HashMap::new();
+	for _ in 0..count {
+		let x = i32::from_le_bytes(take(&mut data, 4)?.try_into().unwrap());
+		let y = i32::from_le_bytes(take(&mut data, 4)?.try_into().unwrap());
+		let len = u32::from_le_bytes(take(&mut data, 4)?.try_into().unwrap());
+		region.insert(Vector::vector([x, y]), Vec::from(take(&mut data, len as usize)?));
+	}
+	Ok(region)
+}
@@ -0,0 +1,92 @@
+use super::*;
+
+use std::fmt;
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct RulesFile {
+	pub blocks: Vec<BlockDef>,
+	pub dug_block: String,
+	pub layers: Vec<LayerDef>,
+	pub depth: DepthNoiseDef,
+	// Rules files from before reach was configurable get the bundled value
+	#[serde(default = "default_reach")]
+	pub reach: f64,
+	#[serde(default)]
+	pub physics: PhysicsRules,
+	#[serde(default)]
+	pub lighting: LightingRules,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BlockDef {
+	pub name: String,
+	pub colour: String,
+	pub brightness_variation: u8,
+	pub colour_variation: u8,
+	pub solid: bool,
+	#[serde(default)]
+	pub emission: u8,
+	// Solid blocks default to Block::SOLID_OPACITY, and others to 0
+	#[serde(default)]
+	pub opacity: Option<u8>,
+}
+
+fn default_reach() -> f64 {
+	5.
+}
+
+// A missing start or end means the layer is unbounded in that direction
+#[derive(Serialize, Deserialize)]
+pub struct LayerDef {
+	pub start: Option<f64>,
+	pub end: Option<f64>,
+	pub blocks: Vec<(String, f64)>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DepthNoiseDef {
+	pub lacunarity: f64,
+	pub persistence: f64,
+	pub frequency: f64,
+	pub amplitude: f64,
+	pub gradient: [f64; 2],
+}
+
+#[derive(Debug)]
+pub enum RulesError {
+	Io(String),
+	Parse(String),
+	TooManyBlocks,
+	InvalidBlockName(String),
+	DuplicateBlock(String),
+	InvalidColour { block: String, colour: String },
+	InvalidOpacity(String),
+	UnknownBlock { context: String, block: String },
+	EmptyLayer(usize),
+	InvalidLayerRange(usize),
+	InvalidWeight { layer: usize, block: String },
+	InvalidValue(String),
+}
+
+impl fmt::Display for RulesError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			RulesError::Io(msg) => write!(f, "Could not read rules: {}", msg),
+			RulesError::Parse(msg) => write!(f, "Could not parse rules: {}", msg),
+			RulesError::TooManyBlocks => write!(f, "Too many blocks, at most {} are allowed", u16::MAX as usize + 1),
+			RulesError::InvalidBlockName(block) => write!(f, "\"{}\" is not a valid block ID, expected \"namespace:name\"", block),
+			RulesError::DuplicateBlock(block) => write!(f, "Block \"{}\" is defined more than once", block),
+			RulesError::InvalidColour { block, colour } => write!(f, "Block \"{}\" has invalid colour \"{}\"", block, colour),
+			RulesError::InvalidOpacity(block) => write!(f, "Block \"{}\" has an opacity over the maximum of {}", block, MAX_LIGHT),
+			RulesError::UnknownBlock { context, block } => write!(f, "{} refers to unknown block \"{}\"", context, block),
+			RulesError::EmptyLayer(layer) => write!(f, "Layer {} has no blocks", layer),
+			RulesError::InvalidLayerRange(layer) => write!(f, "Layer {} ends before it starts", layer),
+			RulesError::InvalidWeight { layer, block } => write!(f, "Layer {} has an invalid weight for block \"{}\"", layer, block),
+			RulesError::InvalidValue(field) => write!(f, "\"{}\" is out of range", field),
+		}
+	}
+}
+
+impl std::error::Error for RulesError { }
@@ -0,0 +1,44 @@
+use std::{
+	collections::HashMap,
+	io,
+};
+
+use sized_matrix::Vector;
+
+// Somewhere to persist encoded chunks. Only modified chunks are ever stored, since unmodified
+// chunks can be regenerated from the world seed. Stores may be buffered until the next flush.
+pub trait ChunkStorage {
+	fn load(&mut self, chunk: Vector<i32, 2>) -> io::Result<Option<Vec<u8>>>;
+	fn store(&mut self, chunk: Vector<i32, 2>, data: &[u8]) -> io::Result<()>;
+	
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+pub struct MemoryStorage {
+	chunks: HashMap<Vector<i32, 2>, Vec<u8>>,
+}
+
+impl MemoryStorage {
+	pub fn new() -> Self {
+		Self {
+			chunks: HashMap::new(),
+		}
+	}
+	
+	pub fn len(&self) -> usize {
+		self.chunks.len()
+	}
+}
+
+impl ChunkStorage for MemoryStorage {
+	fn load(&mut self, chunk: Vector<i32, 2>) -> io::Result<Option<Vec<u8>>> {
+		Ok(self.chunks.get(&chunk).cloned())
+	}
+	
+	fn store(&mut self, chunk: Vector<i32, 2>, data: &[u8]) -> io::Result<()> {
+		self.chunks.insert(chunk, Vec::from(data));
+		Ok(())
+	}
+}
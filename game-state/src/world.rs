@@ -1,6 +1,9 @@
 use super::*;
 
-use std::collections::HashMap;
+use std::{
+	collections::{HashMap, HashSet},
+	io,
+};
 
 use lib::Logger;
 
@@ -10,12 +13,23 @@ use sized_matrix::Vector;
 
 pub struct Chunk {
 	contents: [[u16; Self::SIZE]; Self::SIZE],
+	light: [[u8; Self::SIZE]; Self::SIZE],
+	// One bit per column, for the tiles whose light has changed since they were last taken
+	light_changes: [u64; Self::SIZE],
+	modified: bool,
+	unsaved: bool,
+	last_used: u64,
 }
 
 impl Chunk {
 	pub fn new(contents: [[u16; Self::SIZE]; Self::SIZE]) -> Self {
 		Self {
 			contents,
+			light: [[0; Self::SIZE]; Self::SIZE],
+			light_changes: [0; Self::SIZE],
+			modified: false,
+			unsaved: false,
+			last_used: 0,
 		}
 	}
 	
@@ -23,14 +37,50 @@ impl Chunk {
 		self.contents[pos[1] as usize][pos[0] as usize]
 	}
 	
+	pub fn set(&mut self, pos: Vector<i32, 2>, id: u16) -> u16 {
+		let old = std::mem::replace(&mut self.contents[pos[1] as usize][pos[0] as usize], id);
+		if old != id {
+			self.mark_modified();
+		}
+		old
+	}
+	
+	pub fn light(&self, pos: Vector<i32, 2>) -> u8 {
+		self.light[pos[1] as usize][pos[0] as usize]
+	}
+	
+	// Returns whether the light changed
+	pub fn set_light(&mut self, pos: Vector<i32, 2>, level: u8) -> bool {
+		let old = std::mem::replace(&mut self.light[pos[1] as usize][pos[0] as usize], level);
+		if old != level {
+			self.light_changes[pos[1] as usize] |= 1 << pos[0];
+		}
+		old != level
+	}
+	
+	pub fn is_modified(&self) -> bool {
+		self.modified
+	}
+	
+	pub fn mark_modified(&mut self) {
+		self.modified = true;
+		self.unsaved = true;
+	}
+	
 	pub const SIZE: usize = 64;
 	pub const I_SIZE: i32 = Self::SIZE as i32;
+	pub const BYTES: usize = std::mem::size_of::<Self>();
 }
 
 impl Init<u16, Vector<usize, 2>> for Chunk {
 	fn init_with<F: FnMut(Vector<usize, 2>) -> u16>(_: (), mut elem: F) -> Self {
 		Self {
 			contents: <[_; Self::SIZE]>::init(|y| <[_; Self::SIZE]>::init(|x| elem(Vector::vector([x, y])))),
+			light: [[0; Self::SIZE]; Self::SIZE],
+			light_changes: [0; Self::SIZE],
+			modified: false,
+			unsaved: false,
+			last_used: 0,
 		}
 	}
 }
@@ -39,6 +89,12 @@ pub struct World {
 	rules: &'static GameRules,
 	settings: WorldGenParams,
 	chunks: HashMap<Vector<i32, 2>, Chunk>,
+	changes: Vec<Vector<i32, 2>>,
+	// Chunks with light changes that haven't been taken yet
+	light_changed: HashSet<Vector<i32, 2>>,
+	storage: Option<Box<dyn ChunkStorage>>,
+	memory_budget: usize,
+	clock: u64,
 }
 
 impl World {
@@ -47,25 +103,348 @@ impl World {
 			rules,
 			settings: WorldGenParams::new(rules, seed),
 			chunks: HashMap::new(),
+			changes: Vec::new(),
+			light_changed: HashSet::new(),
+			storage: None,
+			memory_budget: Self::DEFAULT_MEMORY_BUDGET,
+			clock: 0,
 		}
 	}
 	
+	pub fn with_storage(rules: &'static GameRules, seed: u64, storage: Box<dyn ChunkStorage>) -> Self {
+		Self {
+			storage: Some(storage),
+			..Self::new(rules, seed)
+		}
+	}
+	
+	pub fn rules(&self) -> &'static GameRules {
+		self.rules
+	}
+	
+	pub fn depth(&self, pos: Vector<f64, 2>) -> f64 {
+		self.settings.depth(pos)
+	}
+	
+	// Finds the first solid block in the given column, searching down from the top of the surface
+	// noise
+	pub fn surface(&mut self, x: i32) -> i32 {
+		let mut y = Self::SKY_HEIGHT;
+		while !self.get(Vector::vector([x, y])).solid {
+			y += 1;
+		}
+		y
+	}
+	
 	pub fn get(&mut self, pos: Vector<i32, 2>) -> &Block {
-		let chunk_pos = Vector::vector([pos[0].div_euclid(Chunk::I_SIZE), pos[1].div_euclid(Chunk::I_SIZE)]);
-		let local_pos = Vector::vector([pos[0].rem_euclid(Chunk::I_SIZE), pos[1].rem_euclid(Chunk::I_SIZE)]);
-		let id =
-			match self.chunks.get(&chunk_pos) {
-				Some(chunk) => chunk.get(local_pos),
+		let id = self.get_id(pos);
+		self.rules.block(id)
+	}
+	
+	pub fn get_id(&mut self, pos: Vector<i32, 2>) -> u16 {
+		let (chunk_pos, local_pos) = Self::split(pos);
+		self.chunk(chunk_pos).get(local_pos)
+	}
+	
+	pub fn set(&mut self, pos: Vector<i32, 2>, id: u16) -> u16 {
+		let (chunk_pos, local_pos) = Self::split(pos);
+		let old = self.chunk(chunk_pos).set(local_pos, id);
+		if old != id {
+			self.changes.push(pos);
+			update_light(&mut self.lighting(), pos);
+		}
+		old
+	}
+	
+	pub fn dig(&mut self, pos: Vector<i32, 2>) -> Option<u16> {
+		if self.get(pos).solid {
+			Some(self.set(pos, self.rules.dug_block()))
+		} else {
+			None
+		}
+	}
+	
+	pub fn memory_budget(&self) -> usize {
+		self.memory_budget
+	}
+	
+	pub fn set_memory_budget(&mut self, bytes: usize) {
+		self.memory_budget = bytes;
+		self.enforce_memory_budget(None);
+	}
+	
+	pub fn memory_usage(&self) -> usize {
+		self.chunks.len() * Chunk::BYTES
+	}
+	
+	pub fn loaded_chunks(&self) -> usize {
+		self.chunks.len()
+	}
+	
+	pub fn is_loaded(&self, chunk_pos: Vector<i32, 2>) -> bool {
+		self.chunks.contains_key(&chunk_pos)
+	}
+	
+	pub fn is_modified(&self, chunk_pos: Vector<i32, 2>) -> bool {
+		self.chunks.get(&chunk_pos).map_or(false, Chunk::is_modified)
+	}
+	
+	// Lists the cells of a chunk that differ from what the world generator produces
+	pub fn chunk_diff(&mut self, chunk_pos: Vector<i32, 2>) -> Vec<(u16, u16)> {
+		if !self.chunk(chunk_pos).modified {
+			return Vec::new();
+		}
+		let generated = self.rules.generate_chunk(&self.settings, chunk_pos);
+		let chunk = self.chunk(chunk_pos);
+		let mut changes = Vec::new();
+		for y in 0..Chunk::I_SIZE {
+			for x in 0..Chunk::I_SIZE {
+				let local = Vector::vector([x, y]);
+				let id = chunk.get(local);
+				if id != generated.get(local) {
+					changes.push(((y * Chunk::I_SIZE + x) as u16, id));
+				}
+			}
+		}
+		changes
+	}
+	
+	pub fn take_changes(&mut self) -> Vec<Vector<i32, 2>> {
+		std::mem::take(&mut self.changes)
+	}
+	
+	// Light from blocks, or 0 if the chunk isn't loaded
+	pub fn light(&self, pos: Vector<i32, 2>) -> u8 {
+		let (chunk_pos, local_pos) = Self::split(pos);
+		self.chunks.get(&chunk_pos).map_or(0, |chunk| chunk.light(local_pos))
+	}
+	
+	// Tiles in loaded chunks whose light has changed since the last call
+	pub fn take_light_changes(&mut self) -> Vec<Vector<i32, 2>> {
+		let mut changes = Vec::new();
+		for chunk_pos in self.light_changed.drain() {
+			let chunk = match self.chunks.get_mut(&chunk_pos) {
+				Some(chunk) => chunk,
+				None => continue,
+			};
+			for (y, row) in chunk.light_changes.iter_mut().enumerate() {
+				while *row != 0 {
+					let x = row.trailing_zeros() as i32;
+					changes.push(chunk_pos * Chunk::I_SIZE + Vector::vector([x, y as i32]));
+					*row &= *row - 1;
+				}
+			}
+		}
+		changes
+	}
+	
+	// Writes all chunks modified since they were last saved, returning how many were written
+	pub fn save(&mut self) -> io::Result<usize> {
+		let storage = match self.storage.as_mut() {
+			Some(storage) => storage,
+			None => return Ok(0),
+		};
+		let mut count = 0;
+		for (&chunk_pos, chunk) in self.chunks.iter_mut() {
+			if chunk.unsaved {
+				storage.store(chunk_pos, &encode_chunk(chunk, self.rules.blocks()))?;
+				chunk.unsaved = false;
+				count += 1;
+			}
+		}
+		storage.flush()?;
+		Ok(count)
+	}
+	
+	// Removes a chunk from memory, writing it to storage first if needed. Modified chunks are kept
+	// if there is nowhere to write them, since they can't be regenerated.
+	pub fn unload(&mut self, chunk_pos: Vector<i32, 2>) -> bool {
+		let unloaded = self.unload_unflushed(chunk_pos);
+		self.flush();
+		unloaded
+	}
+	
+	// Unloads without flushing storage, so evicting many chunks only writes each region once
+	fn unload_unflushed(&mut self, chunk_pos: Vector<i32, 2>) -> bool {
+		let chunk = match self.chunks.get_mut(&chunk_pos) {
+			Some(chunk) => chunk,
+			None => return false,
+		};
+		if chunk.modified {
+			let storage = match self.storage.as_mut() {
+				Some(storage) => storage,
+				None => return false,
+			};
+			if chunk.unsaved {
+				if let Err(err) = storage.store(chunk_pos, &encode_chunk(chunk, self.rules.blocks())) {
+					Logger::error(&format!("Failed to save ({}, {}): {}", chunk_pos[0], chunk_pos[1], err));
+					return false;
+				}
+			}
+		}
+		
+		// The neighbours can't keep light that came from the chunk, since it can't be fixed up once the chunk is gone
+		let min = chunk_pos * Chunk::I_SIZE;
+		let mut edges = Vec::new();
+		for i in 0..Chunk::I_SIZE {
+			for &local in [[i, 0], [i, Chunk::I_SIZE - 1], [0, i], [Chunk::I_SIZE - 1, i]].iter() {
+				let local = Vector::vector(local);
+				if chunk.light(local) > 0 {
+					edges.push((min + local, chunk.light(local)));
+				}
+			}
+		}
+		self.chunks.remove(&chunk_pos);
+		self.light_changed.remove(&chunk_pos);
+		unlight_area(&mut self.lighting(), edges);
+		true
+	}
+	
+	// Evicts the least recently used chunks once over budget. Evicts down to below the budget so
+	// that walking into new terrain doesn't evict on every new chunk. The kept chunk is never
+	// evicted, even if nothing else can be.
+	fn enforce_memory_budget(&mut self, keep: Option<Vector<i32, 2>>) {
+		let max_chunks = (self.memory_budget / Chunk::BYTES).max(Self::MIN_CHUNKS);
+		if self.chunks.len() <= max_chunks {
+			return;
+		}
+		let target = max_chunks * 3 / 4;
+		
+		let can_unload_modified = self.storage.is_some();
+		let mut candidates: Vec<(u64, Vector<i32, 2>)> = self.chunks.iter()
+			.filter(|(&chunk_pos, chunk)| Some(chunk_pos) != keep && (!chunk.modified || can_unload_modified))
+			.map(|(&chunk_pos, chunk)| (chunk.last_used, chunk_pos))
+			.collect();
+		candidates.sort_by_key(|&(last_used, _)| last_used);
+		
+		for (_, chunk_pos) in candidates {
+			if self.chunks.len() <= target {
+				break;
+			}
+			self.unload_unflushed(chunk_pos);
+		}
+		self.flush();
+		
+		Logger::debug(&format!("Unloaded chunks, {} remaining", self.chunks.len()));
+	}
+	
+	fn flush(&mut self) {
+		if let Some(storage) = self.storage.as_mut() {
+			if let Err(err) = storage.flush() {
+				Logger::error(&format!("Failed to save chunks: {}", err));
+			}
+		}
+	}
+	
+	fn chunk(&mut self, chunk_pos: Vector<i32, 2>) -> &mut Chunk {
+		self.clock += 1;
+		if !self.chunks.contains_key(&chunk_pos) {
+			let chunk = match self.load_chunk(chunk_pos) {
+				Some(chunk) => chunk,
 				None => {
 					Logger::debug(&format!("Generating ({}, {})", chunk_pos[0], chunk_pos[1]));
 					
-					let chunk = self.rules.generate_chunk(&self.settings, chunk_pos);
-					let block = chunk.get(local_pos);
-					self.chunks.insert(chunk_pos, chunk);
-					block
+					self.rules.generate_chunk(&self.settings, chunk_pos)
 				},
 			};
-		let block = self.rules.block(id);
-		block
+			self.chunks.insert(chunk_pos, chunk);
+			light_area(&mut self.lighting(), chunk_pos * Chunk::I_SIZE, Chunk::I_SIZE);
+			let chunk = self.chunks.get_mut(&chunk_pos).unwrap();
+			chunk.last_used = self.clock;
+			// Nothing in the chunk can have been drawn before it was loaded, only light spilling into its neighbours needs redrawing
+			chunk.light_changes = [0; Chunk::SIZE];
+			self.light_changed.remove(&chunk_pos);
+			self.enforce_memory_budget(Some(chunk_pos));
+		}
+		let chunk = self.chunks.get_mut(&chunk_pos).unwrap();
+		chunk.last_used = self.clock;
+		chunk
+	}
+	
+	fn lighting(&mut self) -> ChunkLighting<'_> {
+		ChunkLighting {
+			chunks: &mut self.chunks,
+			changed: &mut self.light_changed,
+			rules: self.rules,
+		}
+	}
+	
+	fn load_chunk(&mut self, chunk_pos: Vector<i32, 2>) -> Option<Chunk> {
+		let data = match self.storage.as_mut()?.load(chunk_pos) {
+			Ok(data) => data?,
+			Err(err) => {
+				Logger::error(&format!("Failed to load ({}, {}): {}", chunk_pos[0], chunk_pos[1], err));
+				return None;
+			},
+		};
+		let decoded = match decode_chunk(&data, self.rules.blocks(), None) {
+			// Blocks removed from the rules are dug out rather than losing the rest of the chunk. The
+			// stored data is left as it is unless the chunk is edited, in case the blocks come back.
+			Err(ChunkFormatError::UnknownBlock(name)) => {
+				Logger::warning(&format!("Replacing unknown block \"{}\" in ({}, {})", name, chunk_pos[0], chunk_pos[1]));
+				decode_chunk(&data, self.rules.blocks(), Some(self.rules.dug_block()))
+			},
+			decoded => decoded,
+		};
+		match decoded {
+			Ok(mut chunk) => {
+				// The chunk already matches what is stored
+				chunk.unsaved = false;
+				Some(chunk)
+			},
+			Err(err) => {
+				Logger::error(&format!("Failed to decode ({}, {}): {}", chunk_pos[0], chunk_pos[1], err));
+				None
+			},
+		}
+	}
+	
+	fn split(pos: Vector<i32, 2>) -> (Vector<i32, 2>, Vector<i32, 2>) {
+		(
+			Vector::vector([pos[0].div_euclid(Chunk::I_SIZE), pos[1].div_euclid(Chunk::I_SIZE)]),
+			Vector::vector([pos[0].rem_euclid(Chunk::I_SIZE), pos[1].rem_euclid(Chunk::I_SIZE)]),
+		)
+	}
+	
+	const SKY_HEIGHT: i32 = -512;
+	const DEFAULT_MEMORY_BUDGET: usize = 32 * 1024 * 1024;
+	// Always keep enough chunks to cover the screen and its surroundings
+	const MIN_CHUNKS: usize = 64;
+}
+
+struct ChunkLighting<'a> {
+	chunks: &'a mut HashMap<Vector<i32, 2>, Chunk>,
+	changed: &'a mut HashSet<Vector<i32, 2>>,
+	rules: &'static GameRules,
+}
+
+impl ChunkLighting<'_> {
+	fn block(&self, pos: Vector<i32, 2>) -> Option<&Block> {
+		let (chunk_pos, local_pos) = World::split(pos);
+		self.chunks.get(&chunk_pos).map(|chunk| self.rules.block(chunk.get(local_pos)))
+	}
+}
+
+impl LightGrid for ChunkLighting<'_> {
+	fn light(&self, pos: Vector<i32, 2>) -> Option<u8> {
+		let (chunk_pos, local_pos) = World::split(pos);
+		self.chunks.get(&chunk_pos).map(|chunk| chunk.light(local_pos))
+	}
+	
+	fn set_light(&mut self, pos: Vector<i32, 2>, level: u8) {
+		let (chunk_pos, local_pos) = World::split(pos);
+		if let Some(chunk) = self.chunks.get_mut(&chunk_pos) {
+			if chunk.set_light(local_pos, level) {
+				self.changed.insert(chunk_pos);
+			}
+		}
+	}
+	
+	fn emission(&self, pos: Vector<i32, 2>) -> u8 {
+		self.block(pos).map_or(0, |block| block.emission)
+	}
+	
+	fn opacity(&self, pos: Vector<i32, 2>) -> u8 {
+		self.block(pos).map_or(0, |block| block.opacity)
 	}
 }
@@ -0,0 +1,122 @@
+use std::{
+	fs,
+	mem,
+	path::PathBuf,
+};
+
+use game_interface::{
+	AssetError,
+	Assets,
+	ImageId,
+	LoadProgress,
+	LoadState,
+};
+
+use sized_matrix::Vector;
+
+use super::{SoftCanvas, SoftImage};
+
+struct Entry {
+	path: String,
+	// Size and ARGB pixels, once loaded
+	image: Option<(Vector<u32, 2>, Vec<u32>)>,
+}
+
+// Loads images from a directory straight away, so nothing is ever left loading
+// Only binary PPM is supported, which is what SoftCanvas saves
+pub struct FileAssets {
+	root: PathBuf,
+	entries: Vec<Entry>,
+	errors: Vec<AssetError>,
+}
+
+impl FileAssets {
+	pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+		Self {
+			root: root.into(),
+			entries: Vec::new(),
+			errors: Vec::new(),
+		}
+	}
+}
+
+impl Assets<SoftCanvas> for FileAssets {
+	fn load_image(&mut self, path: &str) -> ImageId {
+		if let Some(index) = self.entries.iter().position(|entry| entry.path == path) {
+			return ImageId(index);
+		}
+		
+		let image = fs::read(self.root.join(path))
+			.map_err(|err| err.to_string())
+			.and_then(|data| parse_ppm(&data));
+		let image = match image {
+			Ok(image) => Some(image),
+			Err(reason) => {
+				self.errors.push(AssetError::new(path, &reason));
+				None
+			},
+		};
+		self.entries.push(Entry {
+			path: String::from(path),
+			image,
+		});
+		ImageId(self.entries.len() - 1)
+	}
+	
+	fn poll(&mut self) { }
+	
+	fn state(&self, id: ImageId) -> LoadState {
+		if self.entries[id.0].image.is_some() { LoadState::Loaded } else { LoadState::Failed }
+	}
+	
+	fn image(&self, id: ImageId) -> Option<SoftImage> {
+		self.entries[id.0].image.as_ref().map(|(size, pixels)| SoftImage::new(*size, pixels))
+	}
+	
+	fn progress(&self) -> LoadProgress {
+		let loaded = self.entries.iter().filter(|entry| entry.image.is_some()).count();
+		LoadProgress {
+			loaded,
+			failed: self.entries.len() - loaded,
+			total: self.entries.len(),
+		}
+	}
+	
+	fn take_errors(&mut self) -> Vec<AssetError> {
+		mem::take(&mut self.errors)
+	}
+}
+
+// Binary PPM with a maximum value of 255, as written by SoftCanvas::write_ppm
+fn parse_ppm(data: &[u8]) -> Result<(Vector<u32, 2>, Vec<u32>), String> {
+	let mut pos = 0;
+	let mut header = Vec::new();
+	while header.len() < 4 {
+		// Skip whitespace and comments between fields
+		while pos < data.len() && (data[pos].is_ascii_whitespace() || data[pos] == b'#') {
+			if data[pos] == b'#' {
+				while pos < data.len() && data[pos] != b'\n' { pos += 1; }
+			} else {
+				pos += 1;
+			}
+		}
+		let start = pos;
+		while pos < data.len() && !data[pos].is_ascii_whitespace() { pos += 1; }
+		if start == pos { return Err(String::from("Truncated PPM header")); }
+		header.push(String::from_utf8_lossy(&data[start..pos]).into_owned());
+	}
+	// A single whitespace character separates the header from the pixels
+	pos += 1;
+	
+	if header[0] != "P6" { return Err(String::from("Only binary PPM images are supported")); }
+	let number = |text: &str| text.parse::<u32>().map_err(|_| format!("Invalid PPM header value {}", text));
+	let (width, height, max) = (number(&header[1])?, number(&header[2])?, number(&header[3])?);
+	if max != 255 { return Err(String::from("Only 8 bit PPM images are supported")); }
+	
+	let count = width as usize * height as usize;
+	let pixels = data.get(pos..).filter(|rest| rest.len() >= count * 3).ok_or_else(|| String::from("Truncated PPM pixels"))?;
+	let pixels = pixels.chunks(3).take(count)
+		.map(|rgb| 0xFF00_0000 | (rgb[0] as u32) << 16 | (rgb[1] as u32) << 8 | rgb[2] as u32)
+		.collect();
+	Ok((Vector::vector([width, height]), pixels))
+}
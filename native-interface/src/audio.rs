@@ -0,0 +1,93 @@
+use std::mem;
+
+use game_interface::{
+	AssetError,
+	Audio,
+	Channel,
+	ClipId,
+	Sound,
+	TrackId,
+};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum AudioEvent {
+	Played { clip: String, channel: Channel, sound: Sound },
+	LoopStarted { track: TrackId, clip: String, channel: Channel, sound: Sound },
+	TrackChanged { track: TrackId, sound: Sound },
+	Stopped(TrackId),
+	MasterVolume(f64),
+	ChannelVolume(Channel, f64),
+}
+
+// Plays nothing, but keeps what would have been played so tests can check it
+pub struct RecordingAudio {
+	clips: Vec<String>,
+	tracks: usize,
+	events: Vec<AudioEvent>,
+}
+
+impl RecordingAudio {
+	pub fn new() -> Self {
+		Self {
+			clips: Vec::new(),
+			tracks: 0,
+			events: Vec::new(),
+		}
+	}
+	
+	pub fn events(&self) -> &[AudioEvent] {
+		&self.events
+	}
+	
+	pub fn take_events(&mut self) -> Vec<AudioEvent> {
+		mem::take(&mut self.events)
+	}
+}
+
+impl Audio for RecordingAudio {
+	fn start(&self) { }
+	
+	fn load_clip(&mut self, path: &str) -> ClipId {
+		match self.clips.iter().position(|clip| clip == path) {
+			Some(index) => ClipId(index),
+			None => {
+				self.clips.push(String::from(path));
+				ClipId(self.clips.len() - 1)
+			},
+		}
+	}
+	
+	fn poll(&mut self) { }
+	
+	fn play(&mut self, clip: ClipId, channel: Channel, sound: Sound) {
+		self.events.push(AudioEvent::Played { clip: self.clips[clip.0].clone(), channel, sound });
+	}
+	
+	fn play_loop(&mut self, clip: ClipId, channel: Channel, sound: Sound) -> TrackId {
+		let track = TrackId(self.tracks);
+		self.tracks += 1;
+		self.events.push(AudioEvent::LoopStarted { track, clip: self.clips[clip.0].clone(), channel, sound });
+		track
+	}
+	
+	fn set_track(&mut self, track: TrackId, sound: Sound) {
+		self.events.push(AudioEvent::TrackChanged { track, sound });
+	}
+	
+	fn stop(&mut self, track: TrackId) {
+		self.events.push(AudioEvent::Stopped(track));
+	}
+	
+	fn set_master_volume(&mut self, volume: f64) {
+		self.events.push(AudioEvent::MasterVolume(volume));
+	}
+	
+	fn set_channel_volume(&mut self, channel: Channel, volume: f64) {
+		self.events.push(AudioEvent::ChannelVolume(channel, volume));
+	}
+	
+	// Clips are never really loaded, so never fail
+	fn take_errors(&mut self) -> Vec<AssetError> {
+		Vec::new()
+	}
+}
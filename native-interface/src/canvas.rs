@@ -0,0 +1,361 @@
+use std::{
+	fs::File,
+	io::{self, BufWriter, Write},
+	path::Path,
+};
+
+use game_interface::{
+	Canvas,
+	Font,
+	Image,
+	SmoothingQuality,
+	TextAlign,
+	TextBaseline,
+	TextMetrics,
+};
+
+use lib::Colour;
+
+use sized_matrix::Vector;
+use higher_order_functions::Map;
+
+use super::BitmapFont;
+
+// Software implementation of the 2d canvas, for running the client without a browser
+// Pixels are stored row by row as ARGB, the same layout as u32::from(Colour)
+pub struct SoftCanvas {
+	size: Vector<u32, 2>,
+	pixels: Vec<u32>,
+	smoothing: bool,
+	pixel_ratio: f64,
+	resized: bool,
+	font: Font,
+	text_align: TextAlign,
+	text_baseline: TextBaseline,
+}
+
+impl SoftCanvas {
+	pub fn new(size: Vector<u32, 2>) -> Self {
+		Self {
+			size,
+			pixels: vec![0; size[0] as usize * size[1] as usize],
+			smoothing: true,
+			pixel_ratio: 1.,
+			resized: false,
+			// The browser's defaults
+			font: Font::new("sans-serif", 10.),
+			text_align: TextAlign::Left,
+			text_baseline: TextBaseline::Alphabetic,
+		}
+	}
+	
+	pub fn pixel(&self, pos: Vector<u32, 2>) -> Colour {
+		Colour::from(self.pixels[self.index(pos[0], pos[1])])
+	}
+	
+	pub fn pixels(&self) -> &[u32] {
+		&self.pixels
+	}
+	
+	// Clears the canvas, like resizing a browser canvas does
+	pub fn resize(&mut self, size: Vector<u32, 2>, pixel_ratio: f64) {
+		self.size = size;
+		self.pixels = vec![0; size[0] as usize * size[1] as usize];
+		self.pixel_ratio = pixel_ratio;
+		self.resized = true;
+	}
+	
+	pub fn clear(&mut self) {
+		for pixel in self.pixels.iter_mut() {
+			*pixel = 0;
+		}
+	}
+	
+	// Binary PPM, with transparent pixels composited over black
+	pub fn write_ppm<W: Write>(&self, mut out: W) -> io::Result<()> {
+		write!(out, "P6\n{} {}\n255\n", self.size[0], self.size[1])?;
+		let mut row = Vec::with_capacity(self.size[0] as usize * 3);
+		for y in 0..self.size[1] {
+			row.clear();
+			for x in 0..self.size[0] {
+				let colour = Colour::from(self.pixels[self.index(x, y)]);
+				for channel in [colour.r, colour.g, colour.b].iter() {
+					row.push((*channel as u32 * colour.a as u32 / 255) as u8);
+				}
+			}
+			out.write_all(&row)?;
+		}
+		out.flush()
+	}
+	
+	pub fn save_ppm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+		self.write_ppm(BufWriter::new(File::create(path)?))
+	}
+	
+	fn index(&self, x: u32, y: u32) -> usize {
+		y as usize * self.size[0] as usize + x as usize
+	}
+	
+	fn blend(&mut self, x: u32, y: u32, src: [f64; 4]) {
+		let index = self.index(x, y);
+		let dst = premultiply(self.pixels[index]);
+		let mut out = [0.; 4];
+		for i in 0..4 {
+			out[i] = src[i] + dst[i] * (1. - src[3]);
+		}
+		self.pixels[index] = unpremultiply(out);
+	}
+	
+	// Pixels are included when their centre is inside the destination, then sampled at the matching point of the source
+	fn draw(&mut self, image: SoftImage, source_pos: Vector<f64, 2>, source_size: Vector<f64, 2>, dest_pos: Vector<f64, 2>, dest_size: Vector<f64, 2>) {
+		let (source_pos, source_size) = normalise(source_pos, source_size);
+		let (dest_pos, dest_size) = normalise(dest_pos, dest_size);
+		if source_size[0] == 0. || source_size[1] == 0. || dest_size[0] == 0. || dest_size[1] == 0. { return; }
+		
+		// Samples never read outside the source rectangle or the image
+		let bounds_min = Vector::vector([
+			f64::max(source_pos[0].floor(), 0.) as i64,
+			f64::max(source_pos[1].floor(), 0.) as i64,
+		]);
+		let bounds_max = Vector::vector([
+			f64::min((source_pos[0] + source_size[0]).ceil(), image.size[0] as f64) as i64 - 1,
+			f64::min((source_pos[1] + source_size[1]).ceil(), image.size[1] as f64) as i64 - 1,
+		]);
+		if bounds_min[0] > bounds_max[0] || bounds_min[1] > bounds_max[1] { return; }
+		
+		let (x_min, x_max) = self.centre_range(dest_pos[0], dest_size[0], self.size[0]);
+		let (y_min, y_max) = self.centre_range(dest_pos[1], dest_size[1], self.size[1]);
+		
+		for y in y_min..y_max {
+			let v = source_pos[1] + (y as f64 + 0.5 - dest_pos[1]) * source_size[1] / dest_size[1];
+			for x in x_min..x_max {
+				let u = source_pos[0] + (x as f64 + 0.5 - dest_pos[0]) * source_size[0] / dest_size[0];
+				let sample =
+					if self.smoothing { image.sample_bilinear(u, v, bounds_min, bounds_max) }
+					else { image.sample_nearest(u, v, bounds_min, bounds_max) };
+				if let Some(sample) = sample {
+					self.blend(x, y, sample);
+				}
+			}
+		}
+	}
+	
+	// Size of one font pixel, so the glyph cells are as tall as the font
+	fn text_scale(&self) -> f64 {
+		self.font.size / BitmapFont::CELL_HEIGHT as f64
+	}
+	
+	fn centre_range(&self, pos: f64, size: f64, max: u32) -> (u32, u32) {
+		let min = (pos - 0.5).ceil().clamp(0., max as f64) as u32;
+		let end = (pos + size - 0.5).ceil().clamp(0., max as f64) as u32;
+		(min, end)
+	}
+}
+
+impl Image for SoftCanvas {
+	fn size(&self) -> Vector<u32, 2> {
+		self.size
+	}
+}
+
+impl Canvas for SoftCanvas {
+	type TImage<'a> = SoftImage<'a>;
+	
+	fn create(size: Vector<u32, 2>) -> Self {
+		Self::new(size)
+	}
+	
+	// There's only one smoothing algorithm, so all qualities other than None use it
+	fn set_smoothing_quality(&mut self, quality: SmoothingQuality) {
+		self.smoothing = !matches!(quality, SmoothingQuality::None);
+	}
+	
+	fn pixel_ratio(&self) -> f64 {
+		self.pixel_ratio
+	}
+	
+	fn take_resize(&mut self) -> Option<Vector<u32, 2>> {
+		if std::mem::replace(&mut self.resized, false) { Some(self.size) } else { None }
+	}
+	
+	// Edges are antialiased by how much of each pixel the rectangle covers
+	fn fill_rect(&mut self, colour: Colour, pos: Vector<f64, 2>, size: Vector<f64, 2>) {
+		let (pos, size) = normalise(pos, size);
+		let end = pos + size;
+		let x_min = pos[0].floor().clamp(0., self.size[0] as f64) as u32;
+		let x_max = end[0].ceil().clamp(0., self.size[0] as f64) as u32;
+		let y_min = pos[1].floor().clamp(0., self.size[1] as f64) as u32;
+		let y_max = end[1].ceil().clamp(0., self.size[1] as f64) as u32;
+		let colour = premultiply(u32::from(colour));
+		
+		for y in y_min..y_max {
+			let cover_y = f64::min(end[1], y as f64 + 1.) - f64::max(pos[1], y as f64);
+			for x in x_min..x_max {
+				let cover_x = f64::min(end[0], x as f64 + 1.) - f64::max(pos[0], x as f64);
+				let coverage = cover_x * cover_y;
+				if coverage > 0. {
+					self.blend(x, y, [colour[0] * coverage, colour[1] * coverage, colour[2] * coverage, colour[3] * coverage]);
+				}
+			}
+		}
+	}
+	
+	fn set_font(&mut self, font: &Font) {
+		self.font = font.clone();
+	}
+	
+	fn set_text_align(&mut self, align: TextAlign) {
+		self.text_align = align;
+	}
+	
+	fn set_text_baseline(&mut self, baseline: TextBaseline) {
+		self.text_baseline = baseline;
+	}
+	
+	// Always uses the bundled bitmap font, whatever the family
+	fn fill_text(&mut self, colour: Colour, text: &str, pos: Vector<f64, 2>) {
+		let scale = self.text_scale();
+		let metrics = self.measure_text(text);
+		let left = match self.text_align {
+			TextAlign::Left => pos[0],
+			TextAlign::Centre => pos[0] - metrics.width / 2.,
+			TextAlign::Right => pos[0] - metrics.width,
+		};
+		let top = match self.text_baseline {
+			TextBaseline::Top => pos[1],
+			TextBaseline::Middle => pos[1] - self.font.size / 2.,
+			TextBaseline::Alphabetic => pos[1] - metrics.ascent,
+			TextBaseline::Bottom => pos[1] - self.font.size,
+		};
+		
+		for (index, character) in text.chars().enumerate() {
+			let glyph = BitmapFont::glyph(character);
+			let cell = left + (index as u32 * BitmapFont::CELL_WIDTH) as f64 * scale;
+			for y in 0..BitmapFont::GLYPH_HEIGHT {
+				for x in 0..BitmapFont::GLYPH_WIDTH {
+					if BitmapFont::is_set(glyph, x, y) {
+						self.fill_rect(colour, Vector::vector([cell + x as f64 * scale, top + y as f64 * scale]), Vector::vector([scale, scale]));
+					}
+				}
+			}
+		}
+	}
+	
+	fn measure_text(&self, text: &str) -> TextMetrics {
+		let scale = self.text_scale();
+		TextMetrics {
+			width: (text.chars().count() as u32 * BitmapFont::CELL_WIDTH) as f64 * scale,
+			ascent: BitmapFont::GLYPH_HEIGHT as f64 * scale,
+			descent: (BitmapFont::CELL_HEIGHT - BitmapFont::GLYPH_HEIGHT) as f64 * scale,
+		}
+	}
+	
+	fn draw_image(&mut self, image: SoftImage, pos: Vector<f64, 2>) {
+		let size = image.size.map(f64::from);
+		self.draw(image, Vector::vector([0., 0.]), size, pos, size);
+	}
+	
+	fn draw_image_scaled(&mut self, image: SoftImage, pos: Vector<f64, 2>, size: Vector<f64, 2>) {
+		let source_size = image.size.map(f64::from);
+		self.draw(image, Vector::vector([0., 0.]), source_size, pos, size);
+	}
+	
+	fn draw_image_segment_scaled(&mut self, image: SoftImage, source_pos: Vector<f64, 2>, source_size: Vector<f64, 2>, dest_pos: Vector<f64, 2>, dest_size: Vector<f64, 2>) {
+		self.draw(image, source_pos, source_size, dest_pos, dest_size);
+	}
+	
+	fn draw_self(&mut self, pos: Vector<f64, 2>) {
+		let size = self.size.map(f64::from);
+		self.draw_self_segment_scaled(Vector::vector([0., 0.]), size, pos, size);
+	}
+	
+	fn draw_self_scaled(&mut self, pos: Vector<f64, 2>, size: Vector<f64, 2>) {
+		let source_size = self.size.map(f64::from);
+		self.draw_self_segment_scaled(Vector::vector([0., 0.]), source_size, pos, size);
+	}
+	
+	// Reads from a copy so overlapping source and destination behave like the browser
+	fn draw_self_segment_scaled(&mut self, source_pos: Vector<f64, 2>, source_size: Vector<f64, 2>, dest_pos: Vector<f64, 2>, dest_size: Vector<f64, 2>) {
+		let pixels = self.pixels.clone();
+		let image = SoftImage::new(self.size, &pixels);
+		self.draw(image, source_pos, source_size, dest_pos, dest_size);
+	}
+	
+	fn as_image(&self) -> SoftImage {
+		SoftImage::new(self.size, &self.pixels)
+	}
+}
+
+pub struct SoftImage<'a> {
+	size: Vector<u32, 2>,
+	pixels: &'a [u32],
+}
+
+impl<'a> SoftImage<'a> {
+	pub fn new(size: Vector<u32, 2>, pixels: &'a [u32]) -> Self {
+		assert_eq!(pixels.len(), size[0] as usize * size[1] as usize, "Image size doesn't match pixel count");
+		Self {
+			size,
+			pixels,
+		}
+	}
+	
+	fn get(&self, x: i64, y: i64) -> [f64; 4] {
+		premultiply(self.pixels[y as usize * self.size[0] as usize + x as usize])
+	}
+	
+	fn sample_nearest(&self, u: f64, v: f64, min: Vector<i64, 2>, max: Vector<i64, 2>) -> Option<[f64; 4]> {
+		let x = u.floor() as i64;
+		let y = v.floor() as i64;
+		if x < min[0] || x > max[0] || y < min[1] || y > max[1] { return None; }
+		Some(self.get(x, y))
+	}
+	
+	fn sample_bilinear(&self, u: f64, v: f64, min: Vector<i64, 2>, max: Vector<i64, 2>) -> Option<[f64; 4]> {
+		let u = u - 0.5;
+		let v = v - 0.5;
+		let x0 = u.floor();
+		let y0 = v.floor();
+		let tx = u - x0;
+		let ty = v - y0;
+		let clamp = |value: i64, axis: usize| value.max(min[axis]).min(max[axis]);
+		let x0 = x0 as i64;
+		let y0 = y0 as i64;
+		let (xa, xb) = (clamp(x0, 0), clamp(x0 + 1, 0));
+		let (ya, yb) = (clamp(y0, 1), clamp(y0 + 1, 1));
+		let (p00, p10, p01, p11) = (self.get(xa, ya), self.get(xb, ya), self.get(xa, yb), self.get(xb, yb));
+		let mut result = [0.; 4];
+		for i in 0..4 {
+			let top = p00[i] * (1. - tx) + p10[i] * tx;
+			let bottom = p01[i] * (1. - tx) + p11[i] * tx;
+			result[i] = top * (1. - ty) + bottom * ty;
+		}
+		Some(result)
+	}
+}
+
+impl Image for SoftImage<'_> {
+	fn size(&self) -> Vector<u32, 2> {
+		self.size
+	}
+}
+
+fn normalise(pos: Vector<f64, 2>, size: Vector<f64, 2>) -> (Vector<f64, 2>, Vector<f64, 2>) {
+	let flip = |pos: f64, size: f64| if size < 0. { (pos + size, -size) } else { (pos, size) };
+	let (x, width) = flip(pos[0], size[0]);
+	let (y, height) = flip(pos[1], size[1]);
+	(Vector::vector([x, y]), Vector::vector([width, height]))
+}
+
+fn premultiply(pixel: u32) -> [f64; 4] {
+	let colour = Colour::from(pixel);
+	let a = colour.a as f64 / 255.;
+	[colour.r as f64 / 255. * a, colour.g as f64 / 255. * a, colour.b as f64 / 255. * a, a]
+}
+
+fn unpremultiply(colour: [f64; 4]) -> u32 {
+	let a = colour[3].clamp(0., 1.);
+	if a == 0. { return 0; }
+	let channel = |value: f64| (value / a * 255.).round().clamp(0., 255.) as u8;
+	u32::from(Colour::rgba(channel(colour[0]), channel(colour[1]), channel(colour[2]), (a * 255.).round() as u8))
+}
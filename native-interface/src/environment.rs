@@ -0,0 +1,4 @@
+// Names the state passed to timer callbacks, which can't be written directly when the state contains the timer
+pub trait Environment {
+	type TState;
+}
@@ -0,0 +1,76 @@
+use std::{
+	collections::BTreeSet,
+	mem,
+};
+
+use game_interface::{
+	Gamepad,
+	GamepadAxis,
+	GamepadButton,
+	GamepadEvent,
+};
+
+// Gamepad set by hand, for tests and tools
+pub struct ManualGamepad {
+	connected: BTreeSet<u32>,
+	axes: [f64; 4],
+	buttons: [f64; 17],
+	events: Vec<GamepadEvent>,
+}
+
+impl ManualGamepad {
+	pub fn new() -> Self {
+		Self {
+			connected: BTreeSet::new(),
+			axes: [0.; 4],
+			buttons: [0.; 17],
+			events: Vec::new(),
+		}
+	}
+	
+	pub fn connect(&mut self, index: u32) {
+		if self.connected.insert(index) {
+			self.events.push(GamepadEvent::Connected(index));
+		}
+	}
+	
+	pub fn disconnect(&mut self, index: u32) {
+		if self.connected.remove(&index) {
+			self.events.push(GamepadEvent::Disconnected(index));
+		}
+		if self.connected.is_empty() {
+			self.axes = [0.; 4];
+			self.buttons = [0.; 17];
+		}
+	}
+	
+	pub fn set_axis(&mut self, axis: GamepadAxis, value: f64) {
+		self.axes[axis as usize] = value;
+	}
+	
+	pub fn set_button(&mut self, button: GamepadButton, value: f64) {
+		self.buttons[button as usize] = value;
+	}
+}
+
+impl Gamepad for ManualGamepad {
+	fn start(&self) { }
+	
+	fn poll(&mut self) { }
+	
+	fn is_connected(&self) -> bool {
+		!self.connected.is_empty()
+	}
+	
+	fn axis(&self, axis: GamepadAxis) -> f64 {
+		if self.is_connected() { self.axes[axis as usize] } else { 0. }
+	}
+	
+	fn button(&self, button: GamepadButton) -> f64 {
+		if self.is_connected() { self.buttons[button as usize] } else { 0. }
+	}
+	
+	fn take_events(&mut self) -> Vec<GamepadEvent> {
+		mem::take(&mut self.events)
+	}
+}
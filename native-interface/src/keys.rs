@@ -0,0 +1,120 @@
+use std::{
+	cell::RefCell,
+	collections::{HashMap, HashSet},
+};
+
+use game_interface::Keys;
+
+use super::{KeyAction, Timeline};
+
+// Plays back a timeline, call next_frame after each game frame
+pub struct ScriptedKeys {
+	timeline: Timeline,
+	next_event: usize,
+	frame: u64,
+	pressed: HashSet<String>,
+}
+
+impl ScriptedKeys {
+	pub fn new(timeline: Timeline) -> Self {
+		let mut keys = Self {
+			timeline,
+			next_event: 0,
+			frame: 0,
+			pressed: HashSet::new(),
+		};
+		keys.apply_events();
+		keys
+	}
+	
+	pub fn frame(&self) -> u64 {
+		self.frame
+	}
+	
+	pub fn is_finished(&self) -> bool {
+		self.next_event >= self.timeline.events().len()
+	}
+	
+	pub fn next_frame(&mut self) {
+		self.frame += 1;
+		self.apply_events();
+	}
+	
+	fn apply_events(&mut self) {
+		let events = self.timeline.events();
+		while self.next_event < events.len() && events[self.next_event].frame <= self.frame {
+			let event = &events[self.next_event];
+			match event.action {
+				KeyAction::Down => self.pressed.insert(event.key.clone()),
+				KeyAction::Up => self.pressed.remove(&event.key),
+			};
+			self.next_event += 1;
+		}
+	}
+}
+
+impl Keys for ScriptedKeys {
+	fn start(&self) { }
+	
+	fn is_pressed(&self, key: &str) -> bool {
+		self.pressed.contains(key)
+	}
+}
+
+// Records every change the game sees in the keys it asks about, so the same frames can be replayed with ScriptedKeys
+pub struct RecordingKeys<TKeys: Keys> {
+	keys: TKeys,
+	frame: u64,
+	seen: RefCell<HashMap<String, bool>>,
+	timeline: RefCell<Timeline>,
+}
+
+impl<TKeys: Keys> RecordingKeys<TKeys> {
+	pub fn new(keys: TKeys) -> Self {
+		Self {
+			keys,
+			frame: 0,
+			seen: RefCell::new(HashMap::new()),
+			timeline: RefCell::new(Timeline::new()),
+		}
+	}
+	
+	pub fn keys(&self) -> &TKeys {
+		&self.keys
+	}
+	
+	pub fn keys_mut(&mut self) -> &mut TKeys {
+		&mut self.keys
+	}
+	
+	pub fn frame(&self) -> u64 {
+		self.frame
+	}
+	
+	pub fn next_frame(&mut self) {
+		self.frame += 1;
+	}
+	
+	pub fn timeline(&self) -> Timeline {
+		self.timeline.borrow().clone()
+	}
+}
+
+impl<TKeys: Keys> Keys for RecordingKeys<TKeys> {
+	fn start(&self) {
+		self.keys.start();
+	}
+	
+	fn is_pressed(&self, key: &str) -> bool {
+		let pressed = self.keys.is_pressed(key);
+		let mut seen = self.seen.borrow_mut();
+		// Keys start released, so only presses need recording the first time a key is checked
+		let previous = seen.get(key).copied().unwrap_or(false);
+		if pressed != previous {
+			let action = if pressed { KeyAction::Down } else { KeyAction::Up };
+			self.timeline.borrow_mut().push(self.frame, action, key);
+		}
+		seen.insert(String::from(key), pressed);
+		pressed
+	}
+}
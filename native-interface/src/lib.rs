@@ -0,0 +1,238 @@
+#![feature(generic_associated_types)]
+
+mod assets;
+pub use assets::*;
+
+mod audio;
+pub use audio::*;
+
+mod canvas;
+pub use canvas::*;
+
+mod environment;
+pub use environment::*;
+
+mod font;
+use font::*;
+
+mod gamepad;
+pub use gamepad::*;
+
+mod keys;
+pub use keys::*;
+
+mod pointer;
+pub use pointer::*;
+
+mod storage;
+pub use storage::*;
+
+mod timeline;
+pub use timeline::*;
+
+mod timer;
+pub use timer::*;
+
+mod touch;
+pub use touch::*;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	use game_interface::{Assets, Canvas, Font, Keys, LoadProgress, LoadState, SmoothingQuality, SpriteAtlas, TextAlign, TextBaseline, Timer};
+	use std::str::FromStr;
+	use lib::Colour;
+	use sized_matrix::Vector;
+	
+	const RED: Colour = Colour::rgb(255, 0, 0);
+	const BLUE: Colour = Colour::rgb(0, 0, 255);
+	
+	fn v(x: f64, y: f64) -> Vector<f64, 2> {
+		Vector::vector([x, y])
+	}
+	
+	fn pixel(canvas: &SoftCanvas, x: u32, y: u32) -> u32 {
+		u32::from(canvas.pixel(Vector::vector([x, y])))
+	}
+	
+	#[test]
+	fn fill_rect_covers_pixels() {
+		let mut canvas = SoftCanvas::create(Vector::vector([4, 4]));
+		canvas.fill_rect(RED, v(1., 1.), v(2., 2.));
+		
+		assert_eq!(pixel(&canvas, 0, 0), 0);
+		assert_eq!(pixel(&canvas, 1, 1), 0xFFFF0000);
+		assert_eq!(pixel(&canvas, 2, 2), 0xFFFF0000);
+		assert_eq!(pixel(&canvas, 3, 3), 0);
+		
+		canvas.fill_rect(BLUE, v(0., 0.), v(0.5, 1.));
+		assert_eq!(pixel(&canvas, 0, 0), 0x800000FF);
+	}
+	
+	#[test]
+	fn draw_self_scrolls() {
+		let mut canvas = SoftCanvas::create(Vector::vector([3, 1]));
+		canvas.fill_rect(RED, v(0., 0.), v(1., 1.));
+		canvas.fill_rect(BLUE, v(1., 0.), v(1., 1.));
+		canvas.draw_self(v(1., 0.));
+		
+		assert_eq!(pixel(&canvas, 0, 0), 0xFFFF0000);
+		assert_eq!(pixel(&canvas, 1, 0), 0xFFFF0000);
+		assert_eq!(pixel(&canvas, 2, 0), 0xFF0000FF);
+	}
+	
+	#[test]
+	fn scaling_respects_smoothing() {
+		let mut source = SoftCanvas::create(Vector::vector([2, 1]));
+		source.fill_rect(RED, v(0., 0.), v(1., 1.));
+		source.fill_rect(BLUE, v(1., 0.), v(1., 1.));
+		
+		let mut sharp = SoftCanvas::create(Vector::vector([4, 1]));
+		sharp.set_smoothing_quality(SmoothingQuality::None);
+		sharp.draw_image_scaled(source.as_image(), v(0., 0.), v(4., 1.));
+		assert_eq!(sharp.pixels(), &[0xFFFF0000, 0xFFFF0000, 0xFF0000FF, 0xFF0000FF]);
+		
+		let mut smooth = SoftCanvas::create(Vector::vector([4, 1]));
+		smooth.set_smoothing_quality(SmoothingQuality::High);
+		smooth.draw_image_scaled(source.as_image(), v(0., 0.), v(4., 1.));
+		assert_eq!(smooth.pixels(), &[0xFFFF0000, 0xFFBF0040, 0xFF4000BF, 0xFF0000FF]);
+	}
+	
+	#[test]
+	fn segment_is_clipped_to_source() {
+		let mut source = SoftCanvas::create(Vector::vector([2, 2]));
+		source.fill_rect(RED, v(0., 0.), v(2., 2.));
+		
+		let mut canvas = SoftCanvas::create(Vector::vector([4, 4]));
+		canvas.set_smoothing_quality(SmoothingQuality::None);
+		canvas.draw_image_segment_scaled(source.as_image(), v(1., 1.), v(2., 2.), v(0., 0.), v(4., 4.));
+		
+		assert_eq!(pixel(&canvas, 0, 0), 0xFFFF0000);
+		assert_eq!(pixel(&canvas, 1, 1), 0xFFFF0000);
+		assert_eq!(pixel(&canvas, 2, 2), 0);
+		
+		let mut ppm = Vec::new();
+		canvas.write_ppm(&mut ppm).unwrap();
+		assert!(ppm.starts_with(b"P6\n4 4\n255\n"));
+		assert_eq!(ppm.len(), 11 + 4 * 4 * 3);
+	}
+	
+	struct Log;
+	
+	impl Environment for Log {
+		type TState = Vec<String>;
+	}
+	
+	#[test]
+	fn text_fits_measured_box() {
+		let mut canvas = SoftCanvas::create(Vector::vector([40, 20]));
+		canvas.set_font(&Font::new("monospace", 16.));
+		canvas.set_text_align(TextAlign::Right);
+		canvas.set_text_baseline(TextBaseline::Top);
+		let metrics = canvas.measure_text("Hi");
+		assert_eq!(metrics.width, 24.);
+		assert_eq!(metrics.ascent + metrics.descent, 16.);
+		
+		canvas.fill_text(RED, "Hi", v(30., 2.));
+		
+		// The left column of the H, and nothing outside the text box
+		assert_eq!(pixel(&canvas, 6, 2), 0xFFFF0000);
+		assert_eq!(pixel(&canvas, 6, 15), 0xFFFF0000);
+		for (x, y) in [(5, 2), (6, 1), (6, 16), (30, 2)].iter() {
+			assert_eq!(pixel(&canvas, *x, *y), 0);
+		}
+	}
+	
+	#[test]
+	fn saved_image_loads_as_sprite() {
+		let mut sheet = SoftCanvas::create(Vector::vector([4, 2]));
+		sheet.fill_rect(RED, v(0., 0.), v(2., 2.));
+		sheet.fill_rect(BLUE, v(2., 0.), v(2., 2.));
+		let root = std::env::temp_dir().join(format!("native-interface-assets-{}", std::process::id()));
+		std::fs::create_dir_all(&root).unwrap();
+		sheet.save_ppm(root.join("sheet.ppm")).unwrap();
+		
+		let mut assets = FileAssets::new(&root);
+		let image = assets.load_image("sheet.ppm");
+		let missing = assets.load_image("missing.ppm");
+		std::fs::remove_dir_all(&root).unwrap();
+		assert_eq!(assets.load_image("sheet.ppm"), image);
+		assert_eq!(assets.state(missing), LoadState::Failed);
+		assert_eq!(assets.progress(), LoadProgress { loaded: 1, failed: 1, total: 2 });
+		assert_eq!(assets.take_errors().len(), 1);
+		
+		let atlas = SpriteAtlas::grid("sheet.ppm", 2, Vector::vector([2, 2]), &[Some("red"), Some("blue")]);
+		let mut canvas = SoftCanvas::create(Vector::vector([4, 4]));
+		assert!(atlas.draw(&mut canvas, &assets, image, "blue", v(0., 0.), v(4., 4.)));
+		assert!(!atlas.draw(&mut canvas, &assets, missing, "red", v(0., 0.), v(4., 4.)));
+		assert!(canvas.pixels().iter().all(|pixel| *pixel == 0xFF0000FF));
+	}
+	
+	#[test]
+	fn timers_fire_in_order() {
+		let clock = ManualClock::<Log>::new();
+		let mut timer = clock.timer();
+		let mut log = Vec::new();
+		
+		let _late = timer.set_timeout(30, |log: &mut Vec<String>| log.push(String::from("timeout 30")));
+		let _early = timer.set_timeout(10, |log: &mut Vec<String>| log.push(String::from("timeout 10")));
+		let _interval = timer.set_interval(12, |log: &mut Vec<String>| log.push(String::from("interval")));
+		let cancelled = timer.set_timeout(5, |log: &mut Vec<String>| log.push(String::from("cancelled")));
+		drop(cancelled);
+		
+		clock.advance(&mut log, 30.);
+		assert_eq!(log, vec!["timeout 10", "interval", "interval", "timeout 30"]);
+		assert_eq!(clock.now(), 30.);
+		assert_eq!(clock.pending(), 1);
+	}
+	
+	#[test]
+	fn animations_repeat_each_frame() {
+		let mut clock = ManualClock::<Log>::new();
+		clock.set_frame_time(10.);
+		let mut timer = clock.timer();
+		let mut log = Vec::new();
+		
+		let _animation = timer.set_animation(|log: &mut Vec<String>, time| log.push(format!("animation {}", time)));
+		let _frame = timer.set_frame(|log: &mut Vec<String>, time| log.push(format!("frame {}", time)));
+		
+		clock.run_frames(&mut log, 2);
+		assert_eq!(log, vec!["animation 10", "frame 10", "animation 20"]);
+	}
+	
+	#[test]
+	fn timeline_round_trips() {
+		let text = "# Dig down\n0 down s\n\n30 down d\n45 up s\n12 down  \n";
+		let timeline = Timeline::from_str(text).unwrap();
+		
+		assert_eq!(timeline.events()[1], KeyEvent { frame: 12, action: KeyAction::Down, key: String::from(" ") });
+		assert_eq!(timeline.to_string(), "0 down s\n12 down  \n30 down d\n45 up s\n");
+		assert_eq!(Timeline::from_str(&timeline.to_string()).unwrap(), timeline);
+		assert!(Timeline::from_str("3 press a").is_err());
+	}
+	
+	#[test]
+	fn recording_replays_scripted_keys() {
+		fn run<TKeys: Keys>(keys: &mut TKeys, mut next_frame: impl FnMut(&mut TKeys)) -> Vec<(bool, bool)> {
+			let mut pressed = Vec::new();
+			for _ in 0..12 {
+				pressed.push((keys.is_pressed("a"), keys.is_pressed("d")));
+				next_frame(keys);
+			}
+			pressed
+		}
+		
+		let script = Timeline::from_str("0 down a\n5 down d\n5 up a\n9 up d\n").unwrap();
+		let mut recording = RecordingKeys::new(ScriptedKeys::new(script));
+		let original = run(&mut recording, |keys| {
+			keys.next_frame();
+			keys.keys_mut().next_frame();
+		});
+		assert_eq!(&original[4..6], &[(true, false), (false, true)]);
+		assert!(recording.keys().is_finished());
+		
+		let mut replay = ScriptedKeys::new(recording.timeline());
+		assert_eq!(run(&mut replay, ScriptedKeys::next_frame), original);
+	}
+}
@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+
+use game_interface::{
+	Pointer,
+	PointerButton,
+};
+
+use sized_matrix::Vector;
+
+// Pointer moved by hand, for tests and tools
+pub struct ManualPointer {
+	position: Option<Vector<f64, 2>>,
+	pressed: HashSet<PointerButton>,
+	wheel: f64,
+}
+
+impl ManualPointer {
+	pub fn new() -> Self {
+		Self {
+			position: None,
+			pressed: HashSet::new(),
+			wheel: 0.,
+		}
+	}
+	
+	pub fn move_to(&mut self, position: Vector<f64, 2>) {
+		self.position = Some(position);
+	}
+	
+	pub fn leave(&mut self) {
+		self.position = None;
+		self.pressed.clear();
+	}
+	
+	pub fn press(&mut self, button: PointerButton) {
+		self.pressed.insert(button);
+	}
+	
+	pub fn release(&mut self, button: PointerButton) {
+		self.pressed.remove(&button);
+	}
+	
+	pub fn scroll(&mut self, lines: f64) {
+		self.wheel += lines;
+	}
+}
+
+impl Pointer for ManualPointer {
+	fn start(&self) { }
+	
+	fn position(&self) -> Option<Vector<f64, 2>> {
+		self.position
+	}
+	
+	fn is_pressed(&self, button: PointerButton) -> bool {
+		self.pressed.contains(&button)
+	}
+	
+	fn take_wheel(&mut self) -> f64 {
+		std::mem::replace(&mut self.wheel, 0.)
+	}
+}
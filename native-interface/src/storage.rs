@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use game_interface::Storage;
+
+pub struct MemoryStorage {
+	values: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryStorage {
+	pub fn new() -> Self {
+		Self {
+			values: HashMap::new(),
+		}
+	}
+	
+	pub fn keys(&self) -> impl Iterator<Item = &str> {
+		self.values.keys().map(String::as_str)
+	}
+}
+
+impl Storage for MemoryStorage {
+	fn load(&self, key: &str) -> Option<Vec<u8>> {
+		self.values.get(key).cloned()
+	}
+	
+	fn store(&mut self, key: &str, data: &[u8]) -> Result<(), String> {
+		self.values.insert(String::from(key), data.to_vec());
+		Ok(())
+	}
+	
+	fn remove(&mut self, key: &str) {
+		self.values.remove(key);
+	}
+}
@@ -0,0 +1,117 @@
+use std::{
+	error::Error,
+	fmt::{self, Display, Formatter},
+	fs,
+	io,
+	path::Path,
+	str::FromStr,
+};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum KeyAction {
+	Down,
+	Up,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct KeyEvent {
+	pub frame: u64,
+	pub action: KeyAction,
+	pub key: String,
+}
+
+// Key presses and releases by frame, stored as text with one `<frame> down|up <key>` per line
+// Everything after the separating space is the key, so " " can be written as `12 down  `
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Timeline {
+	events: Vec<KeyEvent>,
+}
+
+impl Timeline {
+	pub fn new() -> Self {
+		Self {
+			events: Vec::new(),
+		}
+	}
+	
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TimelineError> {
+		let text = fs::read_to_string(path).map_err(|err| TimelineError::Io(err.to_string()))?;
+		Self::from_str(&text)
+	}
+	
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+		fs::write(path, self.to_string())
+	}
+	
+	// Events for the same frame keep the order they were pushed in
+	pub fn push(&mut self, frame: u64, action: KeyAction, key: &str) {
+		let index = self.events.iter().rposition(|event| event.frame <= frame).map_or(0, |index| index + 1);
+		self.events.insert(index, KeyEvent { frame, action, key: String::from(key) });
+	}
+	
+	pub fn events(&self) -> &[KeyEvent] {
+		&self.events
+	}
+	
+	pub fn last_frame(&self) -> Option<u64> {
+		self.events.last().map(|event| event.frame)
+	}
+}
+
+impl FromStr for Timeline {
+	type Err = TimelineError;
+	
+	fn from_str(text: &str) -> Result<Self, TimelineError> {
+		let mut timeline = Self::new();
+		for (index, line) in text.lines().enumerate() {
+			let line = line.trim_end_matches('\r');
+			if line.trim().is_empty() || line.trim_start().starts_with('#') { continue; }
+			
+			let error = |reason: &str| TimelineError::Parse { line: index + 1, reason: String::from(reason) };
+			let mut parts = line.trim_start().splitn(3, ' ');
+			let frame = parts.next()
+				.and_then(|frame| frame.parse::<u64>().ok())
+				.ok_or_else(|| error("Expected a frame number"))?;
+			let action = match parts.next() {
+				Some("down") => KeyAction::Down,
+				Some("up") => KeyAction::Up,
+				_ => return Err(error("Expected down or up")),
+			};
+			let key = parts.next()
+				.filter(|key| !key.is_empty())
+				.ok_or_else(|| error("Expected a key"))?;
+			timeline.push(frame, action, key);
+		}
+		Ok(timeline)
+	}
+}
+
+impl Display for Timeline {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		for event in &self.events {
+			let action = match event.action {
+				KeyAction::Down => "down",
+				KeyAction::Up => "up",
+			};
+			writeln!(f, "{} {} {}", event.frame, action, event.key)?;
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug)]
+pub enum TimelineError {
+	Io(String),
+	Parse { line: usize, reason: String },
+}
+
+impl Display for TimelineError {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			TimelineError::Io(err) => write!(f, "Could not read timeline: {}", err),
+			TimelineError::Parse { line, reason } => write!(f, "Could not parse timeline line {}: {}", line, reason),
+		}
+	}
+}
+
+impl Error for TimelineError { }
@@ -0,0 +1,262 @@
+use std::{
+	cell::RefCell,
+	marker::PhantomData,
+	rc::{
+		Rc,
+		Weak,
+	},
+};
+
+use game_interface::Timer;
+
+use super::Environment;
+
+type Callback<TState> = Box<dyn FnMut(&mut TState)>;
+type FrameCallback<TState> = Box<dyn FnMut(&mut TState, f64)>;
+
+// Timer driven by hand through a ManualClock, so the game can be run deterministically without a browser
+pub struct ManualTimer<Env: 'static + Environment> {
+	queue: Rc<RefCell<Queue<Env::TState>>>,
+}
+
+pub struct ManualClock<Env: 'static + Environment> {
+	queue: Rc<RefCell<Queue<Env::TState>>>,
+	frame_time: f64,
+}
+
+struct Queue<TState> {
+	now: f64,
+	next_id: u64,
+	timers: Vec<TimerEntry<TState>>,
+	frames: Vec<FrameEntry<TState>>,
+	// Set when the callback currently running is cancelled, so it isn't rescheduled
+	running: Option<(u64, bool)>,
+}
+
+struct TimerEntry<TState> {
+	id: u64,
+	due: f64,
+	period: Option<f64>,
+	callback: Callback<TState>,
+}
+
+struct FrameEntry<TState> {
+	id: u64,
+	repeat: bool,
+	callback: FrameCallback<TState>,
+}
+
+impl<TState> Queue<TState> {
+	fn next_id(&mut self) -> u64 {
+		let id = self.next_id;
+		self.next_id += 1;
+		id
+	}
+	
+	// Returns the removed callbacks so they can be dropped after the queue is released
+	fn cancel(&mut self, id: u64) -> (Option<TimerEntry<TState>>, Option<FrameEntry<TState>>) {
+		if let Some((running, cancelled)) = self.running.as_mut() {
+			if *running == id {
+				*cancelled = true;
+			}
+		}
+		(
+			self.timers.iter().position(|entry| entry.id == id).map(|index| self.timers.remove(index)),
+			self.frames.iter().position(|entry| entry.id == id).map(|index| self.frames.remove(index)),
+		)
+	}
+	
+	fn take_due(&mut self, until: f64) -> Option<TimerEntry<TState>> {
+		let mut next: Option<usize> = None;
+		for (index, entry) in self.timers.iter().enumerate() {
+			if entry.due <= until && next.map_or(true, |next| (entry.due, entry.id) < (self.timers[next].due, self.timers[next].id)) {
+				next = Some(index);
+			}
+		}
+		let entry = self.timers.remove(next?);
+		self.now = f64::max(self.now, entry.due);
+		self.running = Some((entry.id, false));
+		Some(entry)
+	}
+	
+	fn take_frame(&mut self, id: u64) -> Option<FrameEntry<TState>> {
+		let index = self.frames.iter().position(|entry| entry.id == id)?;
+		self.running = Some((id, false));
+		Some(self.frames.remove(index))
+	}
+	
+	fn finish(&mut self) -> bool {
+		match self.running.take() {
+			Some((_, cancelled)) => !cancelled,
+			None => false,
+		}
+	}
+}
+
+impl<Env: Environment> ManualClock<Env> {
+	pub fn new() -> Self {
+		Self {
+			queue: Rc::new(RefCell::new(Queue {
+				now: 0.,
+				next_id: 0,
+				timers: Vec::new(),
+				frames: Vec::new(),
+				running: None,
+			})),
+			frame_time: Self::DEFAULT_FRAME_TIME,
+		}
+	}
+	
+	pub fn timer(&self) -> ManualTimer<Env> {
+		ManualTimer {
+			queue: Rc::clone(&self.queue),
+		}
+	}
+	
+	// Milliseconds since the clock was created
+	pub fn now(&self) -> f64 {
+		self.queue.borrow().now
+	}
+	
+	pub fn frame_time(&self) -> f64 {
+		self.frame_time
+	}
+	
+	pub fn set_frame_time(&mut self, ms: f64) {
+		self.frame_time = ms;
+	}
+	
+	pub fn pending(&self) -> usize {
+		let queue = self.queue.borrow();
+		queue.timers.len() + queue.frames.len()
+	}
+	
+	// Fires timeouts and intervals in the order they are due, but not frames
+	pub fn advance(&self, state: &mut Env::TState, ms: f64) {
+		let until = self.now() + ms;
+		loop {
+			let entry = self.queue.borrow_mut().take_due(until);
+			let mut entry = match entry {
+				Some(entry) => entry,
+				None => break,
+			};
+			(entry.callback)(state);
+			let reschedule = self.queue.borrow_mut().finish();
+			if let (true, Some(period)) = (reschedule, entry.period) {
+				entry.due += period;
+				self.queue.borrow_mut().timers.push(entry);
+			}
+		}
+		self.queue.borrow_mut().now = until;
+	}
+	
+	// Advances by one frame, then fires the frames and animations that were requested before it started
+	pub fn next_frame(&self, state: &mut Env::TState) {
+		self.advance(state, self.frame_time);
+		let (time, ids) = {
+			let queue = self.queue.borrow();
+			(queue.now, queue.frames.iter().map(|entry| entry.id).collect::<Vec<u64>>())
+		};
+		for id in ids {
+			let entry = self.queue.borrow_mut().take_frame(id);
+			if let Some(mut entry) = entry {
+				(entry.callback)(state, time);
+				let reschedule = self.queue.borrow_mut().finish();
+				if reschedule && entry.repeat {
+					self.queue.borrow_mut().frames.push(entry);
+				}
+			}
+		}
+	}
+	
+	pub fn run_frames(&self, state: &mut Env::TState, count: usize) {
+		for _ in 0..count {
+			self.next_frame(state);
+		}
+	}
+	
+	const DEFAULT_FRAME_TIME: f64 = 1000. / 60.;
+}
+
+impl<Env: Environment> ManualTimer<Env> {
+	fn add_timer(&mut self, ms: u32, period: bool, callback: Callback<Env::TState>) -> ManualHandle<Env> {
+		let mut queue = self.queue.borrow_mut();
+		let id = queue.next_id();
+		// Like browsers, zero length intervals are clamped so they can't stall the clock
+		let ms = f64::max(ms as f64, if period { 1. } else { 0. });
+		let due = queue.now + ms;
+		queue.timers.push(TimerEntry {
+			id,
+			due,
+			period: if period { Some(ms) } else { None },
+			callback,
+		});
+		ManualHandle::new(id, &self.queue)
+	}
+	
+	fn add_frame(&mut self, repeat: bool, callback: FrameCallback<Env::TState>) -> ManualHandle<Env> {
+		let mut queue = self.queue.borrow_mut();
+		let id = queue.next_id();
+		queue.frames.push(FrameEntry {
+			id,
+			repeat,
+			callback,
+		});
+		ManualHandle::new(id, &self.queue)
+	}
+}
+
+impl<Env: Environment> Timer<Env::TState> for ManualTimer<Env> {
+	type TTimeout = ManualHandle<Env>;
+	type TInterval = ManualHandle<Env>;
+	type TFrame = ManualHandle<Env>;
+	type TAnimation = ManualHandle<Env>;
+	
+	fn set_timeout<F: 'static + FnOnce(&mut Env::TState)>(&mut self, ms: u32, callback: F) -> ManualHandle<Env> {
+		let mut callback = Some(callback);
+		self.add_timer(ms, false, Box::new(move |state| {
+			if let Some(callback) = callback.take() { callback(state) }
+		}))
+	}
+	
+	fn set_interval<F: 'static + FnMut(&mut Env::TState)>(&mut self, ms: u32, callback: F) -> ManualHandle<Env> {
+		self.add_timer(ms, true, Box::new(callback))
+	}
+	
+	fn set_frame<F: 'static + FnOnce(&mut Env::TState, f64)>(&mut self, callback: F) -> ManualHandle<Env> {
+		let mut callback = Some(callback);
+		self.add_frame(false, Box::new(move |state, time| {
+			if let Some(callback) = callback.take() { callback(state, time) }
+		}))
+	}
+	
+	fn set_animation<F: 'static + FnMut(&mut Env::TState, f64)>(&mut self, callback: F) -> ManualHandle<Env> {
+		self.add_frame(true, Box::new(callback))
+	}
+}
+
+// Cancels the callback when dropped, the same as the web handles
+pub struct ManualHandle<Env: 'static + Environment> {
+	id: u64,
+	queue: Weak<RefCell<Queue<Env::TState>>>,
+	__phantom: PhantomData<&'static mut Env>,
+}
+
+impl<Env: Environment> ManualHandle<Env> {
+	fn new(id: u64, queue: &Rc<RefCell<Queue<Env::TState>>>) -> Self {
+		Self {
+			id,
+			queue: Rc::downgrade(queue),
+			__phantom: PhantomData,
+		}
+	}
+}
+
+impl<Env: Environment> Drop for ManualHandle<Env> {
+	fn drop(&mut self) {
+		if let Some(queue) = self.queue.upgrade() {
+			let removed = queue.borrow_mut().cancel(self.id);
+			drop(removed);
+		}
+	}
+}
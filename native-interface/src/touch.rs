@@ -0,0 +1,39 @@
+use game_interface::{
+	Touch,
+	TouchPoint,
+};
+
+use sized_matrix::Vector;
+
+// Touches moved by hand, for tests and tools
+pub struct ManualTouch {
+	touches: Vec<TouchPoint>,
+}
+
+impl ManualTouch {
+	pub fn new() -> Self {
+		Self {
+			touches: Vec::new(),
+		}
+	}
+	
+	// Starts a touch, or moves it if it has already started
+	pub fn touch(&mut self, id: u32, pos: Vector<f64, 2>) {
+		match self.touches.iter_mut().find(|touch| touch.id == id) {
+			Some(touch) => touch.pos = pos,
+			None => self.touches.push(TouchPoint { id, pos }),
+		}
+	}
+	
+	pub fn release(&mut self, id: u32) {
+		self.touches.retain(|touch| touch.id != id);
+	}
+}
+
+impl Touch for ManualTouch {
+	fn start(&self) { }
+	
+	fn touches(&self) -> &[TouchPoint] {
+		&self.touches
+	}
+}
@@ -0,0 +1,59 @@
+use std::fmt;
+
+use peer_server::{
+	PeerId,
+	Signal,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Channel {
+	// Delivered in order, exactly once
+	Reliable,
+	// May be dropped, but never delayed by earlier lost messages
+	Unreliable,
+}
+
+pub trait PeerConnection {
+	fn peer(&self) -> PeerId;
+	
+	fn is_open(&self) -> bool;
+	
+	fn send(&mut self, channel: Channel, data: &[u8]) -> Result<(), PeerError>;
+	
+	fn receive(&mut self) -> Option<(Channel, Vec<u8>)>;
+	
+	fn close(&mut self);
+}
+
+// Creates connections to other peers. Transports that need to negotiate a connection, such as
+// WebRTC, exchange signals with the other peer through the signalling server.
+pub trait Transport {
+	type TConnection: PeerConnection;
+	
+	fn connect(&mut self, peer: PeerId) -> Result<Self::TConnection, PeerError>;
+	
+	fn accept(&mut self) -> Option<Self::TConnection>;
+	
+	fn handle_signal(&mut self, from: PeerId, signal: Signal);
+	
+	fn take_signals(&mut self) -> Vec<(PeerId, Signal)>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PeerError {
+	NotConnected(PeerId),
+	Closed(PeerId),
+	Unreachable(PeerId),
+}
+
+impl fmt::Display for PeerError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			PeerError::NotConnected(peer) => write!(f, "Not connected to peer {}", peer),
+			PeerError::Closed(peer) => write!(f, "Connection to peer {} is closed", peer),
+			PeerError::Unreachable(peer) => write!(f, "Peer {} is unreachable", peer),
+		}
+	}
+}
+
+impl std::error::Error for PeerError { }
@@ -1,7 +1,86 @@
+mod connection;
+pub use connection::*;
+
+mod loopback;
+pub use loopback::*;
+
+mod mesh;
+pub use mesh::*;
+
 #[cfg(test)]
 mod tests {
-    #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
-    }
+	use super::*;
+	
+	fn message(from: u32, channel: Channel, data: &[u8]) -> MeshEvent {
+		MeshEvent::Message { from, channel, data: Vec::from(data) }
+	}
+	
+	#[test]
+	fn peers_exchange_messages() {
+		let network = LoopbackNetwork::new();
+		let mut a = Mesh::new(1, network.transport(1));
+		let mut b = Mesh::new(2, network.transport(2));
+		
+		a.connect(2).unwrap();
+		assert_eq!(a.poll(), vec![MeshEvent::Connected(2)]);
+		assert_eq!(b.poll(), vec![MeshEvent::Connected(1)]);
+		
+		a.send(2, Channel::Reliable, b"hello").unwrap();
+		b.send(1, Channel::Reliable, b"hi").unwrap();
+		assert_eq!(b.poll(), vec![message(1, Channel::Reliable, b"hello")]);
+		assert_eq!(a.poll(), vec![message(2, Channel::Reliable, b"hi")]);
+	}
+	
+	#[test]
+	fn broadcast_reaches_every_peer() {
+		let network = LoopbackNetwork::new();
+		let mut meshes: Vec<_> = (0..3).map(|id| Mesh::new(id, network.transport(id))).collect();
+		
+		meshes[1].connect(0).unwrap();
+		meshes[2].connect(0).unwrap();
+		meshes[2].connect(1).unwrap();
+		for mesh in meshes.iter_mut() {
+			mesh.poll();
+		}
+		
+		assert!(meshes[0].broadcast(Channel::Reliable, b"dig").is_empty());
+		assert_eq!(meshes[1].poll(), vec![message(0, Channel::Reliable, b"dig")]);
+		assert_eq!(meshes[2].poll(), vec![message(0, Channel::Reliable, b"dig")]);
+	}
+	
+	#[test]
+	fn unreliable_messages_can_be_lost() {
+		let network = LoopbackNetwork::new();
+		network.set_unreliable_loss(1.);
+		let mut a = Mesh::new(1, network.transport(1));
+		let mut b = Mesh::new(2, network.transport(2));
+		a.connect(2).unwrap();
+		b.poll();
+		
+		a.send(2, Channel::Unreliable, b"position").unwrap();
+		a.send(2, Channel::Reliable, b"edit").unwrap();
+		assert_eq!(b.poll(), vec![message(1, Channel::Reliable, b"edit")]);
+	}
+	
+	#[test]
+	fn disconnect_is_seen_by_both_sides() {
+		let network = LoopbackNetwork::new();
+		let mut a = Mesh::new(1, network.transport(1));
+		let mut b = Mesh::new(2, network.transport(2));
+		a.connect(2).unwrap();
+		a.poll();
+		b.poll();
+		
+		a.disconnect(2);
+		assert_eq!(a.poll(), vec![MeshEvent::Disconnected(2)]);
+		assert_eq!(b.poll(), vec![MeshEvent::Disconnected(1)]);
+		assert_eq!(b.send(1, Channel::Reliable, b"hello"), Err(PeerError::NotConnected(1)));
+	}
+	
+	#[test]
+	fn connecting_to_unknown_peer_fails() {
+		let network = LoopbackNetwork::new();
+		let mut a = Mesh::new(1, network.transport(1));
+		assert_eq!(a.connect(5), Err(PeerError::Unreachable(5)));
+	}
 }
@@ -0,0 +1,157 @@
+use super::*;
+
+use std::{
+	cell::RefCell,
+	collections::{
+		HashMap,
+		VecDeque,
+	},
+	rc::Rc,
+};
+
+use peer_server::{
+	PeerId,
+	Signal,
+};
+
+// An in-process network, so meshes can be tested without browsers or sockets
+pub struct LoopbackNetwork {
+	state: Rc<RefCell<NetworkState>>,
+}
+
+struct NetworkState {
+	pending: HashMap<PeerId, VecDeque<LoopbackConnection>>,
+	unreliable_loss: f64,
+	random: u64,
+}
+
+impl NetworkState {
+	// Xorshift, so that dropped messages are the same on every run
+	fn should_drop(&mut self) -> bool {
+		self.random ^= self.random << 13;
+		self.random ^= self.random >> 7;
+		self.random ^= self.random << 17;
+		(self.random as f64 / u64::MAX as f64) < self.unreliable_loss
+	}
+}
+
+impl LoopbackNetwork {
+	pub fn new() -> Self {
+		Self {
+			state: Rc::new(RefCell::new(NetworkState {
+				pending: HashMap::new(),
+				unreliable_loss: 0.,
+				random: 0x2545_F491_4F6C_DD1D,
+			})),
+		}
+	}
+	
+	pub fn transport(&self, id: PeerId) -> LoopbackTransport {
+		self.state.borrow_mut().pending.entry(id).or_default();
+		LoopbackTransport {
+			id,
+			network: Rc::clone(&self.state),
+		}
+	}
+	
+	// Fraction of unreliable messages to drop, from 0 to 1
+	pub fn set_unreliable_loss(&self, loss: f64) {
+		self.state.borrow_mut().unreliable_loss = loss;
+	}
+}
+
+pub struct LoopbackTransport {
+	id: PeerId,
+	network: Rc<RefCell<NetworkState>>,
+}
+
+impl Transport for LoopbackTransport {
+	type TConnection = LoopbackConnection;
+	
+	fn connect(&mut self, peer: PeerId) -> Result<LoopbackConnection, PeerError> {
+		let link = Rc::new(RefCell::new(Link {
+			queues: [VecDeque::new(), VecDeque::new()],
+			open: true,
+		}));
+		
+		let remote = LoopbackConnection {
+			peer: self.id,
+			side: 1,
+			link: Rc::clone(&link),
+			network: Rc::clone(&self.network),
+		};
+		self.network.borrow_mut().pending.get_mut(&peer).ok_or(PeerError::Unreachable(peer))?.push_back(remote);
+		
+		Ok(LoopbackConnection {
+			peer,
+			side: 0,
+			link,
+			network: Rc::clone(&self.network),
+		})
+	}
+	
+	fn accept(&mut self) -> Option<LoopbackConnection> {
+		self.network.borrow_mut().pending.get_mut(&self.id)?.pop_front()
+	}
+	
+	fn handle_signal(&mut self, _from: PeerId, _signal: Signal) { }
+	
+	fn take_signals(&mut self) -> Vec<(PeerId, Signal)> {
+		Vec::new()
+	}
+}
+
+impl Drop for LoopbackTransport {
+	fn drop(&mut self) {
+		self.network.borrow_mut().pending.remove(&self.id);
+	}
+}
+
+struct Link {
+	// Messages waiting to be received by each side
+	queues: [VecDeque<(Channel, Vec<u8>)>; 2],
+	open: bool,
+}
+
+pub struct LoopbackConnection {
+	peer: PeerId,
+	side: usize,
+	link: Rc<RefCell<Link>>,
+	network: Rc<RefCell<NetworkState>>,
+}
+
+impl PeerConnection for LoopbackConnection {
+	fn peer(&self) -> PeerId {
+		self.peer
+	}
+	
+	fn is_open(&self) -> bool {
+		self.link.borrow().open
+	}
+	
+	fn send(&mut self, channel: Channel, data: &[u8]) -> Result<(), PeerError> {
+		let mut link = self.link.borrow_mut();
+		if !link.open {
+			return Err(PeerError::Closed(self.peer));
+		}
+		if channel == Channel::Unreliable && self.network.borrow_mut().should_drop() {
+			return Ok(());
+		}
+		link.queues[1 - self.side].push_back((channel, Vec::from(data)));
+		Ok(())
+	}
+	
+	fn receive(&mut self) -> Option<(Channel, Vec<u8>)> {
+		self.link.borrow_mut().queues[self.side].pop_front()
+	}
+	
+	fn close(&mut self) {
+		self.link.borrow_mut().open = false;
+	}
+}
+
+impl Drop for LoopbackConnection {
+	fn drop(&mut self) {
+		self.close();
+	}
+}
@@ -0,0 +1,125 @@
+use super::*;
+
+use std::collections::BTreeMap;
+
+use peer_server::{
+	PeerId,
+	SignalRequest,
+	SignalResponse,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MeshEvent {
+	Connected(PeerId),
+	Disconnected(PeerId),
+	Message { from: PeerId, channel: Channel, data: Vec<u8> },
+}
+
+// A fully connected group of peers. Newly joined peers connect to everyone already in the room,
+// so each pair of peers only has one connection.
+pub struct Mesh<TTransport: Transport> {
+	id: PeerId,
+	transport: TTransport,
+	connections: BTreeMap<PeerId, TTransport::TConnection>,
+	events: Vec<MeshEvent>,
+}
+
+impl<TTransport: Transport> Mesh<TTransport> {
+	pub fn new(id: PeerId, transport: TTransport) -> Self {
+		Self {
+			id,
+			transport,
+			connections: BTreeMap::new(),
+			events: Vec::new(),
+		}
+	}
+	
+	pub fn id(&self) -> PeerId {
+		self.id
+	}
+	
+	pub fn peers(&self) -> Vec<PeerId> {
+		self.connections.keys().copied().collect()
+	}
+	
+	pub fn connect(&mut self, peer: PeerId) -> Result<(), PeerError> {
+		if peer == self.id || self.connections.contains_key(&peer) {
+			return Ok(());
+		}
+		let connection = self.transport.connect(peer)?;
+		self.connections.insert(peer, connection);
+		self.events.push(MeshEvent::Connected(peer));
+		Ok(())
+	}
+	
+	pub fn disconnect(&mut self, peer: PeerId) {
+		if let Some(mut connection) = self.connections.remove(&peer) {
+			connection.close();
+			self.events.push(MeshEvent::Disconnected(peer));
+		}
+	}
+	
+	pub fn send(&mut self, peer: PeerId, channel: Channel, data: &[u8]) -> Result<(), PeerError> {
+		self.connections.get_mut(&peer).ok_or(PeerError::NotConnected(peer))?.send(channel, data)
+	}
+	
+	pub fn broadcast(&mut self, channel: Channel, data: &[u8]) -> Vec<PeerError> {
+		self.connections.values_mut()
+			.filter_map(|connection| connection.send(channel, data).err())
+			.collect()
+	}
+	
+	// Handles a message from the signalling server, returning any requests to send back to it
+	pub fn handle_signalling(&mut self, response: SignalResponse) -> Vec<SignalRequest> {
+		match response {
+			SignalResponse::Joined { id, peers, .. } => {
+				self.id = id;
+				for peer in peers {
+					if let Err(err) = self.connect(peer) {
+						lib::Logger::warning(&format!("Failed to connect to peer {}: {}", peer, err));
+					}
+				}
+			},
+			SignalResponse::PeerLeft { id } => self.disconnect(id),
+			SignalResponse::Relay { from, signal } => self.transport.handle_signal(from, signal),
+			SignalResponse::PeerJoined { .. } => (),
+			SignalResponse::Error { reason } => lib::Logger::warning(&format!("Signalling error: {}", reason)),
+		}
+		self.signalling_requests()
+	}
+	
+	pub fn signalling_requests(&mut self) -> Vec<SignalRequest> {
+		self.transport.take_signals()
+			.into_iter()
+			.map(|(to, signal)| SignalRequest::Relay { to, signal })
+			.collect()
+	}
+	
+	// Accepts incoming connections, drops closed ones, and collects received messages
+	pub fn poll(&mut self) -> Vec<MeshEvent> {
+		while let Some(connection) = self.transport.accept() {
+			let peer = connection.peer();
+			if let Some(mut old) = self.connections.insert(peer, connection) {
+				old.close();
+			} else {
+				self.events.push(MeshEvent::Connected(peer));
+			}
+		}
+		
+		let mut closed = Vec::new();
+		for (&peer, connection) in self.connections.iter_mut() {
+			while let Some((channel, data)) = connection.receive() {
+				self.events.push(MeshEvent::Message { from: peer, channel, data });
+			}
+			if !connection.is_open() {
+				closed.push(peer);
+			}
+		}
+		for peer in closed {
+			self.connections.remove(&peer);
+			self.events.push(MeshEvent::Disconnected(peer));
+		}
+		
+		std::mem::take(&mut self.events)
+	}
+}
@@ -1,7 +1,58 @@
+mod messages;
+pub use messages::*;
+
+mod signalling_server;
+pub use signalling_server::*;
+
 #[cfg(test)]
 mod tests {
-    #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
-    }
+	use super::*;
+	
+	fn joined(server: &mut SignallingServer, id: PeerId, room: &str) -> Vec<(PeerId, SignalResponse)> {
+		server.connect(id);
+		server.handle(id, SignalRequest::Join { room: String::from(room) })
+	}
+	
+	#[test]
+	fn join_announces_existing_peers() {
+		let mut server = SignallingServer::new();
+		joined(&mut server, 1, "mars");
+		let messages = joined(&mut server, 2, "mars");
+		
+		assert_eq!(messages, vec![
+			(1, SignalResponse::PeerJoined { id: 2 }),
+			(2, SignalResponse::Joined { room: String::from("mars"), id: 2, peers: vec![1] }),
+		]);
+	}
+	
+	#[test]
+	fn relays_only_within_room() {
+		let mut server = SignallingServer::new();
+		joined(&mut server, 1, "mars");
+		joined(&mut server, 2, "mars");
+		joined(&mut server, 3, "phobos");
+		
+		let signal = Signal::Offer(String::from("sdp"));
+		assert_eq!(
+			server.handle(1, SignalRequest::Relay { to: 2, signal: signal.clone() }),
+			vec![(2, SignalResponse::Relay { from: 1, signal: signal.clone() })],
+		);
+		assert!(matches!(
+			server.handle(1, SignalRequest::Relay { to: 3, signal }).as_slice(),
+			[(1, SignalResponse::Error { .. })]
+		));
+	}
+	
+	#[test]
+	fn disconnect_leaves_room() {
+		let mut server = SignallingServer::new();
+		joined(&mut server, 1, "mars");
+		joined(&mut server, 2, "mars");
+		
+		assert_eq!(server.disconnect(1), vec![(2, SignalResponse::PeerLeft { id: 1 })]);
+		assert_eq!(server.peers_in("mars"), vec![2]);
+		
+		server.disconnect(2);
+		assert_eq!(server.peers_in("mars"), Vec::<PeerId>::new());
+	}
 }
@@ -0,0 +1,27 @@
+use serde::{Serialize, Deserialize};
+
+pub type PeerId = u32;
+
+// Opaque connection setup data passed between peers, such as WebRTC session descriptions
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Signal {
+	Offer(String),
+	Answer(String),
+	IceCandidate(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SignalRequest {
+	Join { room: String },
+	Leave,
+	Relay { to: PeerId, signal: Signal },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SignalResponse {
+	Joined { room: String, id: PeerId, peers: Vec<PeerId> },
+	PeerJoined { id: PeerId },
+	PeerLeft { id: PeerId },
+	Relay { from: PeerId, signal: Signal },
+	Error { reason: String },
+}
@@ -0,0 +1,102 @@
+use super::*;
+
+use std::collections::{
+	BTreeSet,
+	HashMap,
+};
+
+// Tracks which room each peer is in and relays signals between peers in the same room. It doesn't
+// do any networking itself, instead returning the messages to send so it can sit behind any
+// transport.
+pub struct SignallingServer {
+	rooms: HashMap<String, BTreeSet<PeerId>>,
+	peers: HashMap<PeerId, Option<String>>,
+}
+
+impl SignallingServer {
+	pub fn new() -> Self {
+		Self {
+			rooms: HashMap::new(),
+			peers: HashMap::new(),
+		}
+	}
+	
+	pub fn connect(&mut self, id: PeerId) {
+		self.peers.insert(id, None);
+	}
+	
+	pub fn disconnect(&mut self, id: PeerId) -> Vec<(PeerId, SignalResponse)> {
+		let messages = self.leave(id);
+		self.peers.remove(&id);
+		messages
+	}
+	
+	pub fn room(&self, id: PeerId) -> Option<&str> {
+		self.peers.get(&id)?.as_deref()
+	}
+	
+	pub fn peers_in(&self, room: &str) -> Vec<PeerId> {
+		self.rooms.get(room).map_or_else(Vec::new, |peers| peers.iter().copied().collect())
+	}
+	
+	pub fn handle(&mut self, from: PeerId, request: SignalRequest) -> Vec<(PeerId, SignalResponse)> {
+		if !self.peers.contains_key(&from) {
+			return vec![(from, Self::error("Not connected"))];
+		}
+		
+		match request {
+			SignalRequest::Join { room } => {
+				if room.is_empty() || room.len() > Self::MAX_ROOM_LENGTH {
+					return vec![(from, Self::error("Invalid room name"))];
+				}
+				
+				let mut messages = self.leave(from);
+				
+				let members = self.rooms.entry(room.clone()).or_default();
+				let peers: Vec<PeerId> = members.iter().copied().collect();
+				members.insert(from);
+				self.peers.insert(from, Some(room.clone()));
+				
+				for &peer in &peers {
+					messages.push((peer, SignalResponse::PeerJoined { id: from }));
+				}
+				messages.push((from, SignalResponse::Joined { room, id: from, peers }));
+				messages
+			},
+			SignalRequest::Leave => self.leave(from),
+			SignalRequest::Relay { to, signal } => {
+				match self.room(from) {
+					Some(room) if self.room(to) == Some(room) && to != from => {
+						vec![(to, SignalResponse::Relay { from, signal })]
+					},
+					_ => vec![(from, Self::error("Peer is not in the same room"))],
+				}
+			},
+		}
+	}
+	
+	fn leave(&mut self, id: PeerId) -> Vec<(PeerId, SignalResponse)> {
+		let room = match self.peers.get_mut(&id).and_then(Option::take) {
+			Some(room) => room,
+			None => return Vec::new(),
+		};
+		
+		let mut messages = Vec::new();
+		if let Some(members) = self.rooms.get_mut(&room) {
+			members.remove(&id);
+			for &peer in members.iter() {
+				messages.push((peer, SignalResponse::PeerLeft { id }));
+			}
+			if members.is_empty() {
+				self.rooms.remove(&room);
+			}
+		}
+		messages
+	}
+	
+	fn error(reason: &str) -> SignalResponse {
+		SignalResponse::Error { reason: String::from(reason) }
+	}
+	
+	const MAX_ROOM_LENGTH: usize = 64;
+}
@@ -0,0 +1,89 @@
+use std::mem;
+
+use web_sys::HtmlImageElement;
+
+use game_interface::{
+	AssetError,
+	Assets,
+	ImageId,
+	LoadProgress,
+	LoadState,
+};
+
+use super::{WebCanvas, WebImage};
+
+struct Entry {
+	path: String,
+	element: HtmlImageElement,
+	state: LoadState,
+}
+
+// Image elements load by themselves, so loads are finished by polling rather than listening for events
+pub struct WebAssets {
+	// Relative paths are resolved against this, so assets can be served from elsewhere
+	base: String,
+	entries: Vec<Entry>,
+	errors: Vec<AssetError>,
+}
+
+impl WebAssets {
+	pub fn new(base: &str) -> Self {
+		Self {
+			base: String::from(base),
+			entries: Vec::new(),
+			errors: Vec::new(),
+		}
+	}
+}
+
+impl Assets<WebCanvas> for WebAssets {
+	fn load_image(&mut self, path: &str) -> ImageId {
+		if let Some(index) = self.entries.iter().position(|entry| entry.path == path) {
+			return ImageId(index);
+		}
+		
+		let element = HtmlImageElement::new().unwrap();
+		element.set_src(&format!("{}{}", self.base, path));
+		self.entries.push(Entry {
+			path: String::from(path),
+			element,
+			state: LoadState::Loading,
+		});
+		ImageId(self.entries.len() - 1)
+	}
+	
+	fn poll(&mut self) {
+		for entry in self.entries.iter_mut().filter(|entry| entry.state == LoadState::Loading) {
+			// Images are complete once they have either loaded or failed, and failed images have no size
+			if entry.element.complete() {
+				if entry.element.natural_width() > 0 {
+					entry.state = LoadState::Loaded;
+				} else {
+					entry.state = LoadState::Failed;
+					self.errors.push(AssetError::new(&entry.path, "The image could not be fetched or decoded"));
+				}
+			}
+		}
+	}
+	
+	fn state(&self, id: ImageId) -> LoadState {
+		self.entries[id.0].state
+	}
+	
+	fn image(&self, id: ImageId) -> Option<WebImage> {
+		let entry = &self.entries[id.0];
+		if entry.state == LoadState::Loaded { Some(WebImage::ImageElement(&entry.element)) } else { None }
+	}
+	
+	fn progress(&self) -> LoadProgress {
+		LoadProgress {
+			loaded: self.entries.iter().filter(|entry| entry.state == LoadState::Loaded).count(),
+			failed: self.entries.iter().filter(|entry| entry.state == LoadState::Failed).count(),
+			total: self.entries.len(),
+		}
+	}
+	
+	fn take_errors(&mut self) -> Vec<AssetError> {
+		mem::take(&mut self.errors)
+	}
+}
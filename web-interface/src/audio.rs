@@ -0,0 +1,236 @@
+use std::{
+	cell::RefCell,
+	mem,
+	rc::Rc,
+};
+
+use web_sys::{
+	AudioBuffer,
+	AudioBufferSourceNode,
+	AudioContext,
+	GainNode,
+	Response,
+	StereoPannerNode,
+};
+
+use js_sys::Promise;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use game_interface::{
+	AssetError,
+	Audio,
+	Channel,
+	ClipId,
+	Sound,
+	TrackId,
+};
+
+#[wasm_bindgen]
+extern "C" {
+	// js_sys's Promise only takes callbacks that return nothing, which can't chain promises
+	#[wasm_bindgen(js_name = "Promise")]
+	type ChainedPromise;
+	
+	#[wasm_bindgen(method, js_name = "then")]
+	fn then(this: &ChainedPromise, on_fulfilled: &Closure<dyn FnMut(JsValue) -> JsValue>) -> ChainedPromise;
+	
+	#[wasm_bindgen(method, js_name = "then")]
+	fn then2(this: &ChainedPromise, on_fulfilled: &Closure<dyn FnMut(JsValue) -> JsValue>, on_rejected: &Closure<dyn FnMut(JsValue) -> JsValue>) -> ChainedPromise;
+}
+
+enum Clip {
+	Loading,
+	Loaded(AudioBuffer),
+	Failed,
+}
+
+// The nodes one playing sound goes through, before its channel's gain
+struct Voice {
+	source: AudioBufferSourceNode,
+	gain: GainNode,
+	panner: StereoPannerNode,
+}
+
+impl Voice {
+	fn set(&self, sound: Sound) {
+		self.gain.gain().set_value(sound.volume as f32);
+		self.panner.pan().set_value(sound.pan as f32);
+		self.source.playback_rate().set_value(sound.pitch as f32);
+	}
+}
+
+struct Track {
+	clip: ClipId,
+	channel: Channel,
+	sound: Sound,
+	// None until the clip has loaded
+	voice: Option<Voice>,
+}
+
+pub struct WebAudio {
+	context: AudioContext,
+	master: GainNode,
+	channels: Vec<GainNode>,
+	paths: Vec<String>,
+	// Shared with the promise callbacks, which finish loads in the background
+	clips: Rc<RefCell<Vec<Clip>>>,
+	errors: Rc<RefCell<Vec<AssetError>>>,
+	tracks: Vec<Option<Track>>,
+	
+	load_closures: Vec<Closure<dyn FnMut(JsValue) -> JsValue>>,
+	resume_closure: Closure<dyn FnMut()>,
+}
+
+impl WebAudio {
+	pub fn new() -> Self {
+		let context = AudioContext::new().unwrap();
+		let master = context.create_gain().unwrap();
+		master.connect_with_audio_node(&context.destination()).unwrap();
+		let channels = Channel::ALL.iter().map(|_| {
+			let gain = context.create_gain().unwrap();
+			gain.connect_with_audio_node(&master).unwrap();
+			gain
+		}).collect();
+		let resume_context = context.clone();
+		
+		Self {
+			context,
+			master,
+			channels,
+			paths: Vec::new(),
+			clips: Rc::new(RefCell::new(Vec::new())),
+			errors: Rc::new(RefCell::new(Vec::new())),
+			tracks: Vec::new(),
+			load_closures: Vec::new(),
+			resume_closure: Closure::new(move || {
+				// Fails if the page still isn't allowed to play sound, in which case the next input tries again
+				let _ = resume_context.resume();
+			}),
+		}
+	}
+	
+	fn voice(&self, clip: ClipId, channel: Channel, sound: Sound, looping: bool) -> Option<Voice> {
+		let buffer = match &self.clips.borrow()[clip.0] {
+			Clip::Loaded(buffer) => buffer.clone(),
+			_ => return None,
+		};
+		let voice = Voice {
+			source: self.context.create_buffer_source().unwrap(),
+			gain: self.context.create_gain().unwrap(),
+			panner: self.context.create_stereo_panner().unwrap(),
+		};
+		voice.source.set_buffer(Some(&buffer));
+		voice.source.set_loop(looping);
+		voice.source.connect_with_audio_node(&voice.gain).unwrap();
+		voice.gain.connect_with_audio_node(&voice.panner).unwrap();
+		voice.panner.connect_with_audio_node(&self.channels[channel as usize]).unwrap();
+		voice.set(sound);
+		voice.source.start().unwrap();
+		Some(voice)
+	}
+}
+
+impl Audio for WebAudio {
+	fn start(&self) {
+		let window = web_sys::window().unwrap();
+		for event_type in ["pointerdown", "keydown", "touchend"].iter() {
+			window.add_event_listener_with_callback(event_type, self.resume_closure.as_ref().unchecked_ref()).unwrap();
+		}
+	}
+	
+	fn load_clip(&mut self, path: &str) -> ClipId {
+		if let Some(index) = self.paths.iter().position(|other| other == path) {
+			return ClipId(index);
+		}
+		let index = self.paths.len();
+		self.paths.push(String::from(path));
+		self.clips.borrow_mut().push(Clip::Loading);
+		
+		let on_response: Closure<dyn FnMut(JsValue) -> JsValue> = Closure::new(|response: JsValue| {
+			let response = response.unchecked_into::<Response>();
+			if response.ok() {
+				response.array_buffer().unwrap_or_else(|err| Promise::reject(&err)).into()
+			} else {
+				Promise::reject(&JsValue::from(format!("Server responded with {}", response.status()))).into()
+			}
+		});
+		let context = self.context.clone();
+		let on_data: Closure<dyn FnMut(JsValue) -> JsValue> = Closure::new(move |data: JsValue| {
+			context.decode_audio_data(data.unchecked_ref()).unwrap_or_else(|err| Promise::reject(&err)).into()
+		});
+		let clips = Rc::clone(&self.clips);
+		let on_loaded: Closure<dyn FnMut(JsValue) -> JsValue> = Closure::new(move |buffer: JsValue| {
+			clips.borrow_mut()[index] = Clip::Loaded(buffer.unchecked_into());
+			JsValue::UNDEFINED
+		});
+		let (clips, errors, clip_path) = (Rc::clone(&self.clips), Rc::clone(&self.errors), String::from(path));
+		let on_failed: Closure<dyn FnMut(JsValue) -> JsValue> = Closure::new(move |err: JsValue| {
+			clips.borrow_mut()[index] = Clip::Failed;
+			errors.borrow_mut().push(AssetError::new(&clip_path, &err.as_string().unwrap_or_else(|| format!("{:?}", err))));
+			JsValue::UNDEFINED
+		});
+		
+		web_sys::window().unwrap().fetch_with_str(path)
+			.unchecked_into::<ChainedPromise>()
+			.then(&on_response)
+			.then(&on_data)
+			.then2(&on_loaded, &on_failed);
+		self.load_closures.extend(vec![on_response, on_data, on_loaded, on_failed]);
+		ClipId(index)
+	}
+	
+	fn poll(&mut self) {
+		let waiting: Vec<usize> = self.tracks.iter().enumerate()
+			.filter(|(_, track)| matches!(track, Some(track) if track.voice.is_none()))
+			.map(|(index, _)| index)
+			.collect();
+		for index in waiting {
+			let (clip, channel, sound) = {
+				let track = self.tracks[index].as_ref().unwrap();
+				(track.clip, track.channel, track.sound)
+			};
+			let voice = self.voice(clip, channel, sound, true);
+			self.tracks[index].as_mut().unwrap().voice = voice;
+		}
+	}
+	
+	fn play(&mut self, clip: ClipId, channel: Channel, sound: Sound) {
+		// The nodes are kept alive by the browser until the sound finishes
+		self.voice(clip, channel, sound, false);
+	}
+	
+	fn play_loop(&mut self, clip: ClipId, channel: Channel, sound: Sound) -> TrackId {
+		let voice = self.voice(clip, channel, sound, true);
+		self.tracks.push(Some(Track { clip, channel, sound, voice }));
+		TrackId(self.tracks.len() - 1)
+	}
+	
+	fn set_track(&mut self, track: TrackId, sound: Sound) {
+		if let Some(track) = self.tracks[track.0].as_mut() {
+			track.sound = sound;
+			if let Some(voice) = &track.voice {
+				voice.set(sound);
+			}
+		}
+	}
+	
+	fn stop(&mut self, track: TrackId) {
+		if let Some(Track { voice: Some(voice), .. }) = self.tracks[track.0].take() {
+			voice.source.stop().unwrap();
+		}
+	}
+	
+	fn set_master_volume(&mut self, volume: f64) {
+		self.master.gain().set_value(volume as f32);
+	}
+	
+	fn set_channel_volume(&mut self, channel: Channel, volume: f64) {
+		self.channels[channel as usize].gain().set_value(volume as f32);
+	}
+	
+	fn take_errors(&mut self) -> Vec<AssetError> {
+		mem::take(&mut *self.errors.borrow_mut())
+	}
+}
@@ -9,8 +9,12 @@ use wasm_bindgen::JsCast;
 
 use game_interface::{
 	Canvas,
+	Font,
 	Image,
 	SmoothingQuality,
+	TextAlign,
+	TextBaseline,
+	TextMetrics,
 };
 
 use lib::Colour;
@@ -28,6 +32,9 @@ extern "C" {
 pub struct WebCanvas {
 	canvas: HtmlCanvasElement,
 	context: CanvasRenderingContext2d,
+	// Whether the backing store follows the size the canvas is displayed at
+	responsive: bool,
+	pixel_ratio: f64,
 }
 
 impl WebCanvas {
@@ -40,6 +47,16 @@ impl WebCanvas {
 				.dyn_into::<web_sys::CanvasRenderingContext2d>()
 				.unwrap(),
 			canvas,
+			responsive: false,
+			pixel_ratio: 1.,
+		}
+	}
+	
+	// A canvas sized by CSS, with one backing store pixel per screen pixel
+	pub fn responsive(canvas: HtmlCanvasElement) -> Self {
+		Self {
+			responsive: true,
+			..Self::new(canvas)
 		}
 	}
 }
@@ -72,11 +89,78 @@ impl Canvas for WebCanvas {
 		}
 	}
 	
+	fn pixel_ratio(&self) -> f64 {
+		self.pixel_ratio
+	}
+	
+	// Checked every frame rather than on resize events, since moving to a screen with a different pixel ratio doesn't fire one
+	fn take_resize(&mut self) -> Option<Vector<u32, 2>> {
+		if !self.responsive { return None; }
+		
+		let pixel_ratio = web_sys::window().map_or(1., |window| window.device_pixel_ratio());
+		let size = Vector::vector([
+			(self.canvas.client_width() as f64 * pixel_ratio).round() as u32,
+			(self.canvas.client_height() as f64 * pixel_ratio).round() as u32,
+		]);
+		if size[0] == 0 || size[1] == 0 || (size == self.size() && pixel_ratio == self.pixel_ratio) { return None; }
+		
+		// Resizing resets the context, so keep the settings
+		let smoothing = self.context.image_smoothing_enabled();
+		let font = self.context.font();
+		let text_align = self.context.text_align();
+		let text_baseline = self.context.text_baseline();
+		
+		self.canvas.set_width(size[0]);
+		self.canvas.set_height(size[1]);
+		self.pixel_ratio = pixel_ratio;
+		
+		self.context.set_image_smoothing_enabled(smoothing);
+		self.context.set_font(&font);
+		self.context.set_text_align(&text_align);
+		self.context.set_text_baseline(&text_baseline);
+		Some(size)
+	}
+	
 	fn fill_rect(&mut self, colour: Colour, pos: Vector<f64, 2>, size: Vector<f64, 2>) {
 		self.context.set_fill_style(&String::from(colour).into());
 		self.context.fill_rect(pos[0], pos[1], size[0], size[1]);
 	}
 	
+	fn set_font(&mut self, font: &Font) {
+		self.context.set_font(&font.to_css());
+	}
+	
+	fn set_text_align(&mut self, align: TextAlign) {
+		self.context.set_text_align(match align {
+			TextAlign::Left => "left",
+			TextAlign::Centre => "center",
+			TextAlign::Right => "right",
+		});
+	}
+	
+	fn set_text_baseline(&mut self, baseline: TextBaseline) {
+		self.context.set_text_baseline(match baseline {
+			TextBaseline::Top => "top",
+			TextBaseline::Middle => "middle",
+			TextBaseline::Alphabetic => "alphabetic",
+			TextBaseline::Bottom => "bottom",
+		});
+	}
+	
+	fn fill_text(&mut self, colour: Colour, text: &str, pos: Vector<f64, 2>) {
+		self.context.set_fill_style(&String::from(colour).into());
+		self.context.fill_text(text, pos[0], pos[1]).unwrap();
+	}
+	
+	fn measure_text(&self, text: &str) -> TextMetrics {
+		let metrics = self.context.measure_text(text).unwrap();
+		TextMetrics {
+			width: metrics.width(),
+			ascent: metrics.font_bounding_box_ascent(),
+			descent: metrics.font_bounding_box_descent(),
+		}
+	}
+	
 	fn draw_image(&mut self, image: WebImage, pos: Vector<f64, 2>) {
 		match image {
 			WebImage::ImageElement(img) => self.context.draw_image_with_html_image_element(img, pos[0], pos[1]).unwrap(),
@@ -1,6 +1,6 @@
 use std::marker::Sized;
 
-use super::WebKeys;
+use super::{WebKeys, WebPointer, WebTouch};
 
 pub trait Environment: Sized {
 	type TState;
@@ -10,4 +10,8 @@ pub trait Environment: Sized {
 	fn get_state(&mut self) -> &mut Self::TState;
 	
 	fn get_keys(&mut self) -> &mut WebKeys<Self>;
+	
+	fn get_pointer(&mut self) -> &mut WebPointer<Self>;
+	
+	fn get_touch(&mut self) -> &mut WebTouch<Self>;
 }
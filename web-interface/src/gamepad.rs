@@ -0,0 +1,92 @@
+use std::{
+	collections::BTreeMap,
+	mem,
+};
+
+use wasm_bindgen::JsCast;
+
+use game_interface::{
+	Gamepad,
+	GamepadAxis,
+	GamepadButton,
+	GamepadEvent,
+};
+
+// Polls navigator.getGamepads, which only reports changes when asked, so connections are found by comparing polls
+pub struct WebGamepad {
+	pads: BTreeMap<u32, PadState>,
+	events: Vec<GamepadEvent>,
+}
+
+struct PadState {
+	axes: Vec<f64>,
+	buttons: Vec<f64>,
+}
+
+impl WebGamepad {
+	pub fn new() -> Self {
+		Self {
+			pads: BTreeMap::new(),
+			events: Vec::new(),
+		}
+	}
+	
+	fn read_pads() -> BTreeMap<u32, PadState> {
+		let mut pads = BTreeMap::new();
+		let gamepads = match web_sys::window().map(|window| window.navigator().get_gamepads()) {
+			Some(Ok(gamepads)) => gamepads,
+			_ => return pads,
+		};
+		for gamepad in gamepads.iter() {
+			// Empty slots are null
+			let gamepad = match gamepad.dyn_into::<web_sys::Gamepad>() {
+				Ok(gamepad) if gamepad.connected() => gamepad,
+				_ => continue,
+			};
+			pads.insert(gamepad.index(), PadState {
+				axes: gamepad.axes().iter().map(|axis| axis.as_f64().unwrap_or(0.)).collect(),
+				buttons: gamepad.buttons().iter()
+					.map(|button| button.dyn_into::<web_sys::GamepadButton>().map_or(0., |button| button.value()))
+					.collect(),
+			});
+		}
+		pads
+	}
+}
+
+impl Gamepad for WebGamepad {
+	fn start(&self) { }
+	
+	fn poll(&mut self) {
+		let pads = Self::read_pads();
+		for index in pads.keys().filter(|index| !self.pads.contains_key(index)).copied().collect::<Vec<u32>>() {
+			self.events.push(GamepadEvent::Connected(index));
+		}
+		for index in self.pads.keys().filter(|index| !pads.contains_key(index)).copied().collect::<Vec<u32>>() {
+			self.events.push(GamepadEvent::Disconnected(index));
+		}
+		self.pads = pads;
+	}
+	
+	fn is_connected(&self) -> bool {
+		!self.pads.is_empty()
+	}
+	
+	fn axis(&self, axis: GamepadAxis) -> f64 {
+		let index = axis as usize;
+		self.pads.values()
+			.filter_map(|pad| pad.axes.get(index).copied())
+			.fold(0., |furthest: f64, value| if value.abs() > furthest.abs() { value } else { furthest })
+	}
+	
+	fn button(&self, button: GamepadButton) -> f64 {
+		let index = button as usize;
+		self.pads.values()
+			.filter_map(|pad| pad.buttons.get(index).copied())
+			.fold(0., f64::max)
+	}
+	
+	fn take_events(&mut self) -> Vec<GamepadEvent> {
+		mem::take(&mut self.events)
+	}
+}
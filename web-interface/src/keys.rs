@@ -38,13 +38,13 @@ impl<Env: 'static + Environment> WebKeys<Env> {
 	fn on_key_down(event: KeyboardEvent) {
 		// JS implicitly passes ownership of the environment
 		let env = Env::take_ownership();
-		env.get_keys().keys.insert(event.key());
+		env.get_keys().keys.insert(event.code());
 	}
 	
 	fn on_key_up(event: KeyboardEvent) {
 		// JS implicitly passes ownership of the environment
 		let env = Env::take_ownership();
-		env.get_keys().keys.remove(&event.key());
+		env.get_keys().keys.remove(&event.code());
 	}
 }
 
@@ -1,15 +1,33 @@
 #![feature(generic_associated_types)]
 #![feature(set_stdio)]
 
+mod assets;
+pub use assets::*;
+
+mod audio;
+pub use audio::*;
+
 mod canvas;
 pub use canvas::*;
 
+mod gamepad;
+pub use gamepad::*;
+
 mod keys;
 pub use keys::*;
 
+mod pointer;
+pub use pointer::*;
+
+mod storage;
+pub use storage::*;
+
 mod timer;
 pub use timer::*;
 
+mod touch;
+pub use touch::*;
+
 mod environment;
 pub use environment::*;
 
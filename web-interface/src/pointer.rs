@@ -0,0 +1,157 @@
+use std::marker::PhantomData;
+use web_sys::{
+	HtmlCanvasElement,
+	MouseEvent,
+	WheelEvent,
+};
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use game_interface::{
+	Pointer,
+	PointerButton,
+};
+
+use sized_matrix::Vector;
+
+use super::Environment;
+
+pub struct WebPointer<Env: 'static + Environment> {
+	canvas: HtmlCanvasElement,
+	position: Option<Vector<f64, 2>>,
+	buttons: [bool; 3],
+	wheel: f64,
+	
+	on_move_closure: Closure<dyn FnMut(MouseEvent)>,
+	on_down_closure: Closure<dyn FnMut(MouseEvent)>,
+	on_up_closure: Closure<dyn FnMut(MouseEvent)>,
+	on_leave_closure: Closure<dyn FnMut(MouseEvent)>,
+	on_wheel_closure: Closure<dyn FnMut(WheelEvent)>,
+	on_context_menu_closure: Closure<dyn FnMut(MouseEvent)>,
+	
+	__phantom: PhantomData<&'static mut Env>,
+}
+
+impl<Env: 'static + Environment> WebPointer<Env> {
+	pub fn new(canvas: HtmlCanvasElement) -> Self {
+		Self {
+			canvas,
+			position: None,
+			buttons: [false; 3],
+			wheel: 0.,
+			on_move_closure: Closure::new(&Self::on_move),
+			on_down_closure: Closure::new(&Self::on_down),
+			on_up_closure: Closure::new(&Self::on_up),
+			on_leave_closure: Closure::new(&Self::on_leave),
+			on_wheel_closure: Closure::new(&Self::on_wheel),
+			on_context_menu_closure: Closure::new(&Self::on_context_menu),
+			__phantom: PhantomData,
+		}
+	}
+	
+	// The canvas may be stretched by CSS, so convert from CSS pixels to canvas pixels
+	fn canvas_position(&self, event: &MouseEvent) -> Vector<f64, 2> {
+		let scale = Vector::vector([
+			self.canvas.width() as f64 / i32::max(self.canvas.client_width(), 1) as f64,
+			self.canvas.height() as f64 / i32::max(self.canvas.client_height(), 1) as f64,
+		]);
+		Vector::vector([event.offset_x() as f64 * scale[0], event.offset_y() as f64 * scale[1]])
+	}
+	
+	fn on_move(event: MouseEvent) {
+		// JS implicitly passes ownership of the environment
+		let env = Env::take_ownership();
+		let pointer = env.get_pointer();
+		pointer.position = Some(pointer.canvas_position(&event));
+		// Buttons released outside the canvas are missed, so resync from the held buttons
+		let held = event.buttons();
+		pointer.buttons = [held & 1 != 0, held & 2 != 0, held & 4 != 0];
+	}
+	
+	fn on_down(event: MouseEvent) {
+		// JS implicitly passes ownership of the environment
+		let env = Env::take_ownership();
+		let pointer = env.get_pointer();
+		pointer.position = Some(pointer.canvas_position(&event));
+		if let Some(index) = button_index(event.button()) {
+			pointer.buttons[index] = true;
+		}
+	}
+	
+	fn on_up(event: MouseEvent) {
+		// JS implicitly passes ownership of the environment
+		let env = Env::take_ownership();
+		if let Some(index) = button_index(event.button()) {
+			env.get_pointer().buttons[index] = false;
+		}
+	}
+	
+	fn on_leave(_event: MouseEvent) {
+		// JS implicitly passes ownership of the environment
+		let env = Env::take_ownership();
+		let pointer = env.get_pointer();
+		pointer.position = None;
+		pointer.buttons = [false; 3];
+	}
+	
+	fn on_wheel(event: WheelEvent) {
+		event.prevent_default();
+		// JS implicitly passes ownership of the environment
+		let env = Env::take_ownership();
+		let pointer = env.get_pointer();
+		let lines = match event.delta_mode() {
+			WheelEvent::DOM_DELTA_PIXEL => event.delta_y() / Self::LINE_HEIGHT,
+			WheelEvent::DOM_DELTA_LINE => event.delta_y(),
+			_ => event.delta_y() * pointer.canvas.client_height() as f64 / Self::LINE_HEIGHT,
+		};
+		pointer.wheel += lines;
+	}
+	
+	// Right click places blocks, so don't open the browser menu
+	fn on_context_menu(event: MouseEvent) {
+		event.prevent_default();
+	}
+	
+	const LINE_HEIGHT: f64 = 16.;
+}
+
+impl<Env: 'static + Environment> Pointer for WebPointer<Env> {
+	fn start(&self) {
+		let listen = |event_type: &str, closure: &JsValue| {
+			self.canvas.add_event_listener_with_callback(event_type, closure.unchecked_ref()).unwrap();
+		};
+		listen("mousemove", self.on_move_closure.as_ref());
+		listen("mousedown", self.on_down_closure.as_ref());
+		listen("mouseup", self.on_up_closure.as_ref());
+		listen("mouseleave", self.on_leave_closure.as_ref());
+		listen("wheel", self.on_wheel_closure.as_ref());
+		listen("contextmenu", self.on_context_menu_closure.as_ref());
+	}
+	
+	fn position(&self) -> Option<Vector<f64, 2>> {
+		self.position
+	}
+	
+	fn is_pressed(&self, button: PointerButton) -> bool {
+		self.buttons[match button {
+			PointerButton::Primary => 0,
+			PointerButton::Secondary => 1,
+			PointerButton::Middle => 2,
+		}]
+	}
+	
+	fn take_wheel(&mut self) -> f64 {
+		std::mem::replace(&mut self.wheel, 0.)
+	}
+}
+
+// MouseEvent.button numbers the middle button before the secondary one, unlike MouseEvent.buttons
+fn button_index(button: i16) -> Option<usize> {
+	match button {
+		0 => Some(0),
+		1 => Some(2),
+		2 => Some(1),
+		_ => None,
+	}
+}
@@ -0,0 +1,89 @@
+use std::{
+	collections::HashMap,
+	fmt::Write,
+};
+
+use game_interface::Storage;
+
+use lib::Logger;
+
+// TODO: Switch to IndexedDB once the storage interface is asynchronous
+
+// Persists to window.localStorage, which only holds strings, so data is hex encoded
+// Falls back to memory when localStorage is blocked, such as in private browsing, so the game still runs without saving
+pub struct WebStorage {
+	storage: Option<web_sys::Storage>,
+	memory: HashMap<String, String>,
+	prefix: String,
+}
+
+impl WebStorage {
+	pub fn new(prefix: &str) -> Self {
+		let storage = match web_sys::window().map(|window| window.local_storage()) {
+			Some(Ok(Some(storage))) => Some(storage),
+			Some(Err(err)) => {
+				Logger::warning(&format!("localStorage is blocked, nothing will be saved: {:?}", err));
+				None
+			},
+			_ => {
+				Logger::warning("localStorage is unavailable, nothing will be saved");
+				None
+			},
+		};
+		Self {
+			storage,
+			memory: HashMap::new(),
+			prefix: String::from(prefix),
+		}
+	}
+	
+	fn key(&self, key: &str) -> String {
+		format!("{}{}", self.prefix, key)
+	}
+	
+	fn get(&self, key: &str) -> Option<String> {
+		match &self.storage {
+			Some(storage) => storage.get_item(&self.key(key)).ok()?,
+			None => self.memory.get(key).cloned(),
+		}
+	}
+}
+
+impl Storage for WebStorage {
+	fn load(&self, key: &str) -> Option<Vec<u8>> {
+		let value = self.get(key)?;
+		if value.len() % 2 != 0 {
+			return None;
+		}
+		(0..value.len())
+			.step_by(2)
+			.map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+			.collect()
+	}
+	
+	fn store(&mut self, key: &str, data: &[u8]) -> Result<(), String> {
+		let mut value = String::with_capacity(data.len() * 2);
+		for byte in data {
+			write!(value, "{:02x}", byte).unwrap();
+		}
+		match &self.storage {
+			Some(storage) => storage.set_item(&self.key(key), &value)
+				.map_err(|err| format!("Failed to store {}: {:?}", key, err)),
+			None => {
+				self.memory.insert(String::from(key), value);
+				Ok(())
+			},
+		}
+	}
+	
+	fn remove(&mut self, key: &str) {
+		match &self.storage {
+			Some(storage) => {
+				let _ = storage.remove_item(&self.key(key));
+			},
+			None => {
+				self.memory.remove(key);
+			},
+		}
+	}
+}
@@ -0,0 +1,102 @@
+use std::marker::PhantomData;
+use web_sys::{
+	HtmlCanvasElement,
+	TouchEvent,
+};
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use game_interface::{
+	Touch,
+	TouchPoint,
+};
+
+use sized_matrix::Vector;
+
+use super::Environment;
+
+pub struct WebTouch<Env: 'static + Environment> {
+	canvas: HtmlCanvasElement,
+	touches: Vec<TouchPoint>,
+	
+	on_change_closure: Closure<dyn FnMut(TouchEvent)>,
+	on_end_closure: Closure<dyn FnMut(TouchEvent)>,
+	
+	__phantom: PhantomData<&'static mut Env>,
+}
+
+impl<Env: 'static + Environment> WebTouch<Env> {
+	pub fn new(canvas: HtmlCanvasElement) -> Self {
+		Self {
+			canvas,
+			touches: Vec::new(),
+			on_change_closure: Closure::new(&Self::on_change),
+			on_end_closure: Closure::new(&Self::on_end),
+			__phantom: PhantomData,
+		}
+	}
+	
+	// Touches only have client coordinates, and the canvas may be stretched by CSS
+	fn canvas_position(&self, touch: &web_sys::Touch) -> Vector<f64, 2> {
+		let rect = self.canvas.get_bounding_client_rect();
+		let scale = Vector::vector([
+			self.canvas.width() as f64 / f64::max(rect.width(), 1.),
+			self.canvas.height() as f64 / f64::max(rect.height(), 1.),
+		]);
+		Vector::vector([
+			(touch.client_x() as f64 - rect.left()) * scale[0],
+			(touch.client_y() as f64 - rect.top()) * scale[1],
+		])
+	}
+	
+	fn changed_touches(event: &TouchEvent) -> Vec<web_sys::Touch> {
+		let list = event.changed_touches();
+		(0..list.length()).filter_map(|index| list.get(index)).collect()
+	}
+	
+	// Handles both touchstart and touchmove
+	fn on_change(event: TouchEvent) {
+		// Stop the browser scrolling, zooming, or sending emulated mouse events
+		event.prevent_default();
+		// JS implicitly passes ownership of the environment
+		let env = Env::take_ownership();
+		let touch = env.get_touch();
+		for changed in Self::changed_touches(&event) {
+			let id = changed.identifier() as u32;
+			let pos = touch.canvas_position(&changed);
+			match touch.touches.iter_mut().find(|point| point.id == id) {
+				Some(point) => point.pos = pos,
+				None => touch.touches.push(TouchPoint { id, pos }),
+			}
+		}
+	}
+	
+	// Handles both touchend and touchcancel
+	fn on_end(event: TouchEvent) {
+		event.prevent_default();
+		// JS implicitly passes ownership of the environment
+		let env = Env::take_ownership();
+		let touch = env.get_touch();
+		for changed in Self::changed_touches(&event) {
+			let id = changed.identifier() as u32;
+			touch.touches.retain(|point| point.id != id);
+		}
+	}
+}
+
+impl<Env: 'static + Environment> Touch for WebTouch<Env> {
+	fn start(&self) {
+		let listen = |event_type: &str, closure: &JsValue| {
+			self.canvas.add_event_listener_with_callback(event_type, closure.unchecked_ref()).unwrap();
+		};
+		listen("touchstart", self.on_change_closure.as_ref());
+		listen("touchmove", self.on_change_closure.as_ref());
+		listen("touchend", self.on_end_closure.as_ref());
+		listen("touchcancel", self.on_end_closure.as_ref());
+	}
+	
+	fn touches(&self) -> &[TouchPoint] {
+		&self.touches
+	}
+}
@@ -8,11 +8,18 @@ use web_sys::{
 
 use web_interface::{
 	Environment,
+	WebAudio,
 	WebCanvas,
+	WebGamepad,
 	WebKeys,
+	WebPointer,
+	WebStorage,
 	WebTimer,
+	WebTouch,
 };
 
+use game_client::Platform;
+
 #[wasm_bindgen]
 extern "C" {
 	#[wasm_bindgen(js_namespace = document, js_name = "getElementById")]
@@ -44,6 +51,10 @@ impl Environment for Env {
 		WebTimer<Self>,
 		WebCanvas,
 		WebKeys<Self>,
+		WebPointer<Self>,
+		WebGamepad,
+		WebTouch<Self>,
+		WebAudio,
 	>;
 	
 	// TODO: Run-time checks?
@@ -60,6 +71,14 @@ impl Environment for Env {
 	fn get_keys(&mut self) -> &mut WebKeys<Self> {
 		self.game.keys()
 	}
+	
+	fn get_pointer(&mut self) -> &mut WebPointer<Self> {
+		self.game.pointer()
+	}
+	
+	fn get_touch(&mut self) -> &mut WebTouch<Self> {
+		self.game.touch()
+	}
 }
 
 #[wasm_bindgen(start)]
@@ -74,11 +93,16 @@ pub fn start() {
 		.unwrap();
 	
 	Env::init(Some(Env {
-		game: Game::new(
-			WebTimer::new(),
-			WebCanvas::new(canvas),
-			WebKeys::new(),
-		),
+		game: Game::new(Platform {
+			timer: WebTimer::new(),
+			canvas: WebCanvas::responsive(canvas.clone()),
+			keys: WebKeys::new(),
+			pointer: WebPointer::new(canvas.clone()),
+			gamepad: WebGamepad::new(),
+			touch: WebTouch::new(canvas),
+			audio: WebAudio::new(),
+			storage: WebStorage::new("peer-miner."),
+		}),
 	}));
 	
 	let env = Env::take_ownership();